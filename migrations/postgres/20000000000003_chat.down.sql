@@ -0,0 +1,4 @@
+DROP TABLE moderation_actions;
+DROP TABLE chat_sanctions;
+DROP TABLE messages;
+ALTER TABLE users DROP COLUMN is_moderator;
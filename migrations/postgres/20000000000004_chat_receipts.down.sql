@@ -0,0 +1,2 @@
+DROP TABLE message_reactions;
+DROP TABLE read_markers;
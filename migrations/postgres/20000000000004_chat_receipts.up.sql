@@ -0,0 +1,16 @@
+-- Reference from ReadMarker and ReactionCount structs in types/src/chat.rs
+CREATE TABLE IF NOT EXISTS read_markers (
+  user_uuid VARCHAR(36) NOT NULL,
+  room VARCHAR(64) NOT NULL,
+  message_id BIGINT NOT NULL,
+  updated_at BIGINT NOT NULL,
+  PRIMARY KEY (user_uuid, room)
+);
+
+CREATE TABLE IF NOT EXISTS message_reactions (
+  message_id BIGINT NOT NULL,
+  user_uuid VARCHAR(36) NOT NULL,
+  emoji VARCHAR(32) NOT NULL,
+  created_at BIGINT NOT NULL,
+  PRIMARY KEY (message_id, user_uuid, emoji)
+);
@@ -0,0 +1,18 @@
+-- Reference from Attachment struct in types/src/attachment.rs
+CREATE TABLE IF NOT EXISTS attachments (
+  id VARCHAR(36) PRIMARY KEY,
+  uploader_uuid VARCHAR(36) NOT NULL,
+  message_id BIGINT,
+  file_name VARCHAR(255) NOT NULL,
+  content_type VARCHAR(127) NOT NULL,
+  size BIGINT NOT NULL,
+  hash VARCHAR(64) NOT NULL,
+  storage_key VARCHAR(255) NOT NULL,
+  thumbnail_key VARCHAR(255),
+  width INTEGER,
+  height INTEGER,
+  created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS attachments_hash ON attachments (hash);
+CREATE INDEX IF NOT EXISTS attachments_message_id ON attachments (message_id);
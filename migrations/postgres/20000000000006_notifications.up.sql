@@ -0,0 +1,21 @@
+-- Reference from Notification struct in types/src/notification.rs
+CREATE TABLE IF NOT EXISTS notifications (
+  id VARCHAR(36) PRIMARY KEY,
+  user_uuid VARCHAR(36) NOT NULL,
+  category VARCHAR(16) NOT NULL,
+  title VARCHAR(255) NOT NULL,
+  body TEXT NOT NULL,
+  room VARCHAR(64),
+  message_id BIGINT,
+  read_at BIGINT,
+  created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS notifications_user_uuid_created_at ON notifications (user_uuid, created_at);
+
+CREATE TABLE IF NOT EXISTS notification_opt_outs (
+  user_uuid VARCHAR(36) NOT NULL,
+  category VARCHAR(16) NOT NULL,
+  created_at BIGINT NOT NULL,
+  PRIMARY KEY (user_uuid, category)
+);
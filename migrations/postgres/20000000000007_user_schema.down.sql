@@ -0,0 +1,19 @@
+DROP INDEX users_email_lower_key;
+DROP INDEX users_username_lower_key;
+
+ALTER TABLE users
+  ADD CONSTRAINT users_username_key UNIQUE (username),
+  ADD CONSTRAINT users_email_key UNIQUE (email),
+  DROP COLUMN updated_at,
+  DROP COLUMN created_at,
+  ALTER COLUMN is_moderator DROP NOT NULL,
+  ALTER COLUMN is_disabled DROP NOT NULL,
+  ALTER COLUMN is_admin DROP NOT NULL,
+  ALTER COLUMN is_admin DROP DEFAULT,
+  ALTER COLUMN password DROP NOT NULL,
+  ALTER COLUMN email DROP NOT NULL,
+  ALTER COLUMN username DROP NOT NULL,
+  ALTER COLUMN uuid DROP NOT NULL,
+  ALTER COLUMN uuid TYPE VARCHAR(36) USING uuid::TEXT;
+
+ALTER TABLE users ALTER COLUMN id DROP IDENTITY;
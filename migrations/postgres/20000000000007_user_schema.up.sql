@@ -0,0 +1,26 @@
+-- Reference from User and UserInfo structs in types/src/user.rs
+UPDATE users SET
+  is_admin = COALESCE(is_admin, FALSE),
+  is_disabled = COALESCE(is_disabled, FALSE),
+  is_moderator = COALESCE(is_moderator, FALSE);
+
+ALTER TABLE users ALTER COLUMN id ADD GENERATED BY DEFAULT AS IDENTITY;
+SELECT setval(pg_get_serial_sequence('users', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM users;
+
+ALTER TABLE users
+  ALTER COLUMN uuid TYPE UUID USING uuid::UUID,
+  ALTER COLUMN uuid SET NOT NULL,
+  ALTER COLUMN username SET NOT NULL,
+  ALTER COLUMN email SET NOT NULL,
+  ALTER COLUMN password SET NOT NULL,
+  ALTER COLUMN is_admin SET DEFAULT FALSE,
+  ALTER COLUMN is_admin SET NOT NULL,
+  ALTER COLUMN is_disabled SET NOT NULL,
+  ALTER COLUMN is_moderator SET NOT NULL,
+  ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  DROP CONSTRAINT users_username_key,
+  DROP CONSTRAINT users_email_key;
+
+CREATE UNIQUE INDEX users_username_lower_key ON users (LOWER(username));
+CREATE UNIQUE INDEX users_email_lower_key ON users (LOWER(email));
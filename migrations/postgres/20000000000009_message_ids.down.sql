@@ -0,0 +1 @@
+ALTER TABLE messages ALTER COLUMN id DROP IDENTITY;
@@ -0,0 +1,3 @@
+-- Message ids are generated by the database so they stay unique and ordered across nodes
+ALTER TABLE messages ALTER COLUMN id ADD GENERATED BY DEFAULT AS IDENTITY;
+SELECT setval(pg_get_serial_sequence('messages', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM messages;
@@ -0,0 +1,2 @@
+-- Reference from User struct in types/src/user.rs
+ALTER TABLE users ADD COLUMN is_disabled BOOLEAN DEFAULT FALSE;
@@ -0,0 +1,6 @@
+-- Payloads too large for Postgres NOTIFY, referenced by id from server/src/pubsub.rs
+CREATE TABLE IF NOT EXISTS pubsub_payloads (
+  id VARCHAR(36) PRIMARY KEY,
+  payload TEXT NOT NULL,
+  created_at BIGINT NOT NULL
+);
@@ -0,0 +1,38 @@
+-- Reference from ChatMessage and ModerationAction structs in types/src/chat.rs
+ALTER TABLE users ADD COLUMN is_moderator BOOLEAN DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS messages (
+  id BIGINT PRIMARY KEY,
+  room VARCHAR(64) NOT NULL,
+  user_uuid VARCHAR(36) NOT NULL,
+  username VARCHAR(24) NOT NULL,
+  text TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  edited_at BIGINT,
+  deleted_at BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS messages_room_id ON messages (room, id);
+
+CREATE TABLE IF NOT EXISTS chat_sanctions (
+  id VARCHAR(36) PRIMARY KEY,
+  user_uuid VARCHAR(36) NOT NULL,
+  room VARCHAR(64),
+  kind VARCHAR(8) NOT NULL,
+  expires_at BIGINT,
+  created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS chat_sanctions_user_uuid ON chat_sanctions (user_uuid);
+
+CREATE TABLE IF NOT EXISTS moderation_actions (
+  id VARCHAR(36) PRIMARY KEY,
+  moderator_uuid VARCHAR(36) NOT NULL,
+  target_uuid VARCHAR(36),
+  action VARCHAR(16) NOT NULL,
+  room VARCHAR(64),
+  message_id BIGINT,
+  reason TEXT,
+  expires_at BIGINT,
+  created_at BIGINT NOT NULL
+);
@@ -0,0 +1,16 @@
+CREATE TABLE users_old (
+  id INTEGER PRIMARY KEY UNIQUE,
+  uuid VARCHAR(36) UNIQUE,
+  username VARCHAR(24) UNIQUE,
+  email VARCHAR(254) UNIQUE,
+  password VARCHAR(60),
+  is_admin BOOLEAN,
+  is_disabled BOOLEAN DEFAULT FALSE,
+  is_moderator BOOLEAN DEFAULT FALSE
+);
+
+INSERT INTO users_old (id, uuid, username, email, password, is_admin, is_disabled, is_moderator)
+SELECT id, uuid, username, email, password, is_admin, is_disabled, is_moderator FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_old RENAME TO users;
@@ -0,0 +1,32 @@
+-- Reference from User and UserInfo structs in types/src/user.rs
+-- SQLite cannot alter column types or constraints, so the table is rebuilt
+CREATE TABLE users_new (
+  id INTEGER PRIMARY KEY,
+  uuid VARCHAR(36) NOT NULL UNIQUE CHECK (length(uuid) = 36),
+  username VARCHAR(24) NOT NULL,
+  email VARCHAR(254) NOT NULL,
+  password VARCHAR(60) NOT NULL,
+  is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+  is_disabled BOOLEAN NOT NULL DEFAULT FALSE,
+  is_moderator BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+  updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+INSERT INTO users_new (id, uuid, username, email, password, is_admin, is_disabled, is_moderator)
+SELECT
+  id,
+  LOWER(uuid),
+  username,
+  email,
+  password,
+  COALESCE(is_admin, FALSE),
+  COALESCE(is_disabled, FALSE),
+  COALESCE(is_moderator, FALSE)
+FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE UNIQUE INDEX users_username_lower_key ON users (LOWER(username));
+CREATE UNIQUE INDEX users_email_lower_key ON users (LOWER(email));
@@ -0,0 +1,9 @@
+-- Single-use WebSocket and event stream tickets, redeemed in server/src/strategies/realtime_strategy.rs
+CREATE TABLE IF NOT EXISTS ws_tickets (
+  ticket_hash VARCHAR(64) PRIMARY KEY,
+  user_uuid VARCHAR(36) NOT NULL,
+  token_exp BIGINT NOT NULL,
+  expires_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS ws_tickets_expires_at ON ws_tickets (expires_at);
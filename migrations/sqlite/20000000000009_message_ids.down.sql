@@ -0,0 +1,18 @@
+CREATE TABLE messages_old (
+  id BIGINT PRIMARY KEY,
+  room VARCHAR(64) NOT NULL,
+  user_uuid VARCHAR(36) NOT NULL,
+  username VARCHAR(24) NOT NULL,
+  text TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  edited_at BIGINT,
+  deleted_at BIGINT
+);
+
+INSERT INTO messages_old (id, room, user_uuid, username, text, created_at, edited_at, deleted_at)
+SELECT id, room, user_uuid, username, text, created_at, edited_at, deleted_at FROM messages;
+
+DROP TABLE messages;
+ALTER TABLE messages_old RENAME TO messages;
+
+CREATE INDEX IF NOT EXISTS messages_room_id ON messages (room, id);
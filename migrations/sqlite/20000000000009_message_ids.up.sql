@@ -0,0 +1,20 @@
+-- Message ids are generated by the database so they stay unique and ordered across nodes
+-- SQLite cannot alter column types or constraints, so the table is rebuilt
+CREATE TABLE messages_new (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  room VARCHAR(64) NOT NULL,
+  user_uuid VARCHAR(36) NOT NULL,
+  username VARCHAR(24) NOT NULL,
+  text TEXT NOT NULL,
+  created_at BIGINT NOT NULL,
+  edited_at BIGINT,
+  deleted_at BIGINT
+);
+
+INSERT INTO messages_new (id, room, user_uuid, username, text, created_at, edited_at, deleted_at)
+SELECT id, room, user_uuid, username, text, created_at, edited_at, deleted_at FROM messages;
+
+DROP TABLE messages;
+ALTER TABLE messages_new RENAME TO messages;
+
+CREATE INDEX IF NOT EXISTS messages_room_id ON messages (room, id);
@@ -0,0 +1,706 @@
+use std::any::type_name;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use std::{env, fs};
+
+use tracing_subscriber::EnvFilter;
+
+use crate::dialect::Dialect;
+use crate::pool::DatabaseConfig;
+
+const DEFAULT_CONFIG_FILE: &str = "dash.toml";
+const FILE_SUFFIX: &str = "_FILE";
+const REDACTED: &str = "********";
+
+const LOG_FORMATS: [&str; 2] = ["pretty", "json"];
+const DATABASE_MIGRATION_MODES: [&str; 3] = ["run", "verify", "off"];
+const SQLITE_JOURNAL_MODES: [&str; 6] = ["DELETE", "TRUNCATE", "PERSIST", "MEMORY", "WAL", "OFF"];
+const STORAGE_BACKENDS: [&str; 2] = ["local", "s3"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Origin {
+    Default,
+    File,
+    Env,
+    Flag,
+}
+
+impl Origin {
+    fn name(&self) -> &'static str {
+        match self {
+            Origin::Default => "default",
+            Origin::File => "file",
+            Origin::Env => "env",
+            Origin::Flag => "flag",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ConfigArgs {
+    pub file: Option<PathBuf>,
+    pub flags: HashMap<String, String>,
+    pub command: Vec<String>,
+}
+
+pub struct AuthConfig {
+    pub token_expiry: u64,
+    pub jwt_secret: String,
+    pub jwt_audience: String,
+    pub jwt_issuer: String,
+    pub password_salt: [u8; 16],
+}
+
+pub struct AttachmentConfig {
+    pub max_size: usize,
+    pub types: Vec<String>,
+    pub url_expiry: u64,
+    pub url_secret: String,
+}
+
+pub struct BackupConfig {
+    pub dir: Option<PathBuf>,
+    pub interval: u64,
+    pub retention: usize,
+}
+
+pub struct LogConfig {
+    pub format: String,
+    pub level: String,
+    pub redact: Vec<String>,
+    #[cfg(feature = "otlp")]
+    pub otlp_endpoint: Option<String>,
+    #[cfg(feature = "otlp")]
+    pub service_name: String,
+}
+
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub socket: Option<PathBuf>,
+    pub tls: Option<TlsConfig>,
+    pub shutdown_delay: Duration,
+    pub shutdown_timeout: Duration,
+}
+
+pub struct TlsConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    pub reload_interval: Duration,
+}
+
+pub struct StorageConfig {
+    pub backend: String,
+    pub dir: PathBuf,
+    pub s3_bucket: Option<String>,
+}
+
+pub struct WsConfig {
+    pub channel_capacity: usize,
+    pub handshake_timeout: u64,
+    pub history_size: i64,
+    pub idle_timeout: u64,
+    pub legacy_auth: bool,
+    pub max_sanction_duration: u64,
+    pub outbound_queue: usize,
+    pub ping_interval: u64,
+    pub rate_limit: u32,
+    pub rate_window: u64,
+    pub ticket_expiry: u64,
+}
+
+pub struct Config {
+    pub auth: AuthConfig,
+    pub attachments: AttachmentConfig,
+    pub backup: BackupConfig,
+    pub database: DatabaseConfig,
+    pub log: LogConfig,
+    pub metrics: MetricsConfig,
+    pub server: ServerConfig,
+    pub storage: StorageConfig,
+    pub ws: WsConfig,
+}
+
+struct Setting {
+    key: String,
+    value: Option<String>,
+    origin: Origin,
+    secret: bool,
+}
+
+struct ConfigSource {
+    file: HashMap<String, String>,
+    flags: HashMap<String, String>,
+    used: HashSet<String>,
+    settings: Vec<Setting>,
+    errors: Vec<String>,
+}
+
+impl ConfigSource {
+    fn new(args: &ConfigArgs) -> Self {
+        let mut source = Self {
+            file: HashMap::new(),
+            flags: args.flags.clone(),
+            used: HashSet::new(),
+            settings: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let path = match (&args.file, env::var("CONFIG_FILE")) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Ok(path)) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+        if let Some(path) = path {
+            source.read_file(&path);
+        }
+        source
+    }
+
+    fn read_file(&mut self, path: &Path) {
+        let table = match fs::read_to_string(path) {
+            Ok(content) => match content.parse::<toml::Table>() {
+                Ok(table) => table,
+                Err(error) => {
+                    self.errors.push(format!("Invalid config file {}: {}", path.display(), error));
+                    return;
+                }
+            },
+            Err(error) => {
+                self.errors.push(format!("Cannot read config file {}: {}", path.display(), error));
+                return;
+            }
+        };
+        flatten_table("", &table, &mut self.file, &mut self.errors);
+    }
+
+    fn lookup(&mut self, key: &str) -> Option<(String, Origin)> {
+        let file_key = format!("{}{}", key, FILE_SUFFIX);
+        self.used.insert(key.to_string());
+        self.used.insert(file_key.clone());
+
+        let layers = [
+            (Origin::Flag, self.flags.get(key).cloned(), self.flags.get(&file_key).cloned()),
+            (Origin::Env, env::var(key).ok(), env::var(&file_key).ok()),
+            (Origin::File, self.file.get(key).cloned(), self.file.get(&file_key).cloned()),
+        ];
+        for (origin, value, path) in layers {
+            if let Some(value) = value {
+                return Some((value, origin));
+            }
+            if let Some(path) = path {
+                return match fs::read_to_string(&path) {
+                    Ok(value) => Some((value.trim_end_matches(['\r', '\n']).to_string(), origin)),
+                    Err(error) => {
+                        self.errors.push(format!("Cannot read {} from {}: {}", key, path, error));
+                        None
+                    }
+                };
+            }
+        }
+        None
+    }
+
+    fn value(&mut self, key: &str, secret: bool) -> Option<String> {
+        let found = self.lookup(key).filter(|(value, _)| !value.is_empty());
+        let (value, origin) = match found {
+            Some((value, origin)) => (Some(value), origin),
+            None => (None, Origin::Default),
+        };
+        self.settings.push(Setting { key: key.to_string(), value: value.clone(), origin, secret });
+        value
+    }
+
+    fn string(&mut self, key: &str, default: &str) -> String {
+        match self.value(key, false) {
+            Some(value) => value,
+            None => {
+                self.settings.last_mut().unwrap().value = Some(default.to_string());
+                default.to_string()
+            }
+        }
+    }
+
+    fn required(&mut self, key: &str, secret: bool) -> String {
+        self.value(key, secret).unwrap_or_else(|| {
+            self.errors.push(format!("Missing {}", key));
+            String::new()
+        })
+    }
+
+    fn parse<T: FromStr + Display>(&mut self, key: &str, default: T) -> T {
+        match self.value(key, false) {
+            Some(value) => self.parse_value(key, &value).unwrap_or(default),
+            None => {
+                self.settings.last_mut().unwrap().value = Some(default.to_string());
+                default
+            }
+        }
+    }
+
+    fn parse_required<T: FromStr + Default>(&mut self, key: &str) -> T {
+        match self.value(key, false) {
+            Some(value) => self.parse_value(key, &value).unwrap_or_default(),
+            None => {
+                self.errors.push(format!("Missing {}", key));
+                T::default()
+            }
+        }
+    }
+
+    fn parse_value<T: FromStr>(&mut self, key: &str, value: &str) -> Option<T> {
+        match value.trim().parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.errors.push(format!(
+                    "Cannot parse {} as {}: {}",
+                    key,
+                    type_name::<T>(),
+                    value
+                ));
+                None
+            }
+        }
+    }
+
+    fn check_unknown(&mut self) {
+        let mut unknown = Vec::new();
+        for key in self.file.keys().filter(|key| !self.used.contains(*key)) {
+            unknown.push(format!("Unknown key in config file: {}", key.to_lowercase()));
+        }
+        for key in self.flags.keys().filter(|key| !self.used.contains(*key)) {
+            unknown.push(format!("Unknown flag: --{}", flag_name(key)));
+        }
+        unknown.sort();
+        self.errors.extend(unknown);
+    }
+}
+
+fn flatten_table(
+    prefix: &str,
+    table: &toml::Table,
+    values: &mut HashMap<String, String>,
+    errors: &mut Vec<String>,
+) {
+    for (key, value) in table {
+        let key = key.to_uppercase().replace('-', "_");
+        let key = if prefix.is_empty() { key } else { format!("{}_{}", prefix, key) };
+        let value = match value {
+            toml::Value::Table(table) => {
+                flatten_table(&key, table, values, errors);
+                continue;
+            }
+            toml::Value::String(value) => value.clone(),
+            toml::Value::Array(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| match item {
+                        toml::Value::String(item) => item.clone(),
+                        item => item.to_string(),
+                    })
+                    .collect();
+                items.join(",")
+            }
+            toml::Value::Datetime(_) => {
+                errors.push(format!("Unsupported date value for {}", key.to_lowercase()));
+                continue;
+            }
+            value => value.to_string(),
+        };
+        values.insert(key, value);
+    }
+}
+
+fn flag_name(key: &str) -> String {
+    key.to_lowercase().replace('_', "-")
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<ConfigArgs, String> {
+    let mut config_args = ConfigArgs::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next_if(|arg| arg.starts_with("--")) {
+        let (name, value) = match arg[2..].split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => match args.next() {
+                Some(value) => (arg[2..].to_string(), value),
+                None => return Err(format!("Missing value for flag {}", arg)),
+            },
+        };
+        if name == "config" {
+            config_args.file = Some(PathBuf::from(value));
+        } else {
+            config_args.flags.insert(name.to_uppercase().replace('-', "_"), value);
+        }
+    }
+    config_args.command = args.collect();
+    Ok(config_args)
+}
+
+fn load_auth(source: &mut ConfigSource) -> AuthConfig {
+    let token_expiry = source.parse_required("AUTH_TOKEN_EXPIRY");
+    let jwt_secret = source.required("JWT_SECRET", true);
+    let jwt_audience = source.required("JWT_AUDIENCE", false);
+    let jwt_issuer = source.required("JWT_ISSUER", false);
+    let salt = source.required("PASSWORD_SALT", true);
+    let password_salt = match salt.as_bytes().try_into() {
+        Ok(password_salt) => password_salt,
+        Err(_) => {
+            if !salt.is_empty() {
+                source.errors.push(String::from("PASSWORD_SALT is not 16 characters long"));
+            }
+            [0; 16]
+        }
+    };
+    AuthConfig { token_expiry, jwt_secret, jwt_audience, jwt_issuer, password_salt }
+}
+
+fn load_attachments(source: &mut ConfigSource, jwt_secret: &str) -> AttachmentConfig {
+    let max_size = source.parse("ATTACHMENT_MAX_SIZE", 10 * 1024 * 1024);
+    let types = source
+        .string(
+            "ATTACHMENT_TYPES",
+            "image/png,image/jpeg,image/gif,image/webp,application/pdf,text/plain",
+        )
+        .split(',')
+        .map(|content_type| content_type.trim().to_lowercase())
+        .filter(|content_type| !content_type.is_empty())
+        .collect();
+    let url_expiry = source.parse("ATTACHMENT_URL_EXPIRY", 3600);
+    let url_secret = source.value("ATTACHMENT_URL_SECRET", true).unwrap_or(jwt_secret.to_string());
+    AttachmentConfig { max_size, types, url_expiry, url_secret }
+}
+
+fn load_backup(source: &mut ConfigSource) -> BackupConfig {
+    BackupConfig {
+        dir: source.value("SQLITE_BACKUP_DIR", false).map(PathBuf::from),
+        interval: source.parse("SQLITE_BACKUP_INTERVAL", 86400).max(1),
+        retention: source.parse("SQLITE_BACKUP_RETENTION", 7).max(1),
+    }
+}
+
+fn load_database(source: &mut ConfigSource) -> DatabaseConfig {
+    let url = source.required("DATABASE_URL", true);
+    let dialect = Dialect::from_url(&url).unwrap_or_else(|| {
+        if !url.is_empty() {
+            source.errors.push(String::from(
+                "Unsupported DATABASE_URL scheme, expected postgres:// or sqlite:",
+            ));
+        }
+        Dialect::Sqlite
+    });
+    let migrations = source.string("DATABASE_MIGRATIONS", "run");
+    if !DATABASE_MIGRATION_MODES.contains(&migrations.as_str()) {
+        source.errors.push(format!("Unknown DATABASE_MIGRATIONS mode: {}", migrations));
+    }
+    let sqlite_journal_mode = source.string("SQLITE_JOURNAL_MODE", "wal").to_uppercase();
+    if !SQLITE_JOURNAL_MODES.contains(&sqlite_journal_mode.as_str()) {
+        source.errors.push(format!("Unknown SQLITE_JOURNAL_MODE: {}", sqlite_journal_mode));
+    }
+    let max_connections = source.parse("DATABASE_MAX_CONNECTIONS", 100).max(1);
+    let replica_url = source.value("DATABASE_REPLICA_URL", true);
+    if let Some(replica_url) = &replica_url {
+        if Dialect::from_url(replica_url) != Some(dialect) {
+            source.errors.push(String::from(
+                "DATABASE_REPLICA_URL must use the same backend as DATABASE_URL",
+            ));
+        }
+    }
+
+    DatabaseConfig {
+        url,
+        dialect,
+        migrations,
+        connect_attempts: source.parse("DATABASE_CONNECT_ATTEMPTS", 10).max(1),
+        connect_backoff: Duration::from_millis(source.parse("DATABASE_CONNECT_BACKOFF", 500)),
+        sqlite_journal_mode,
+        sqlite_busy_timeout: Duration::from_millis(source.parse("SQLITE_BUSY_TIMEOUT", 5000)),
+        max_connections,
+        min_connections: source.parse("DATABASE_MIN_CONNECTIONS", 0).min(max_connections),
+        acquire_timeout: Duration::from_millis(source.parse("DATABASE_ACQUIRE_TIMEOUT", 30000)),
+        idle_timeout: Duration::from_millis(source.parse("DATABASE_IDLE_TIMEOUT", 1000)),
+        max_lifetime: Duration::from_secs(source.parse("DATABASE_MAX_LIFETIME", 1800)),
+        statement_timeout: Duration::from_millis(source.parse("DATABASE_STATEMENT_TIMEOUT", 0)),
+        replica_url,
+    }
+}
+
+fn load_log(source: &mut ConfigSource) -> LogConfig {
+    let format = source.string("LOG_FORMAT", "pretty");
+    if !LOG_FORMATS.contains(&format.as_str()) {
+        source.errors.push(format!("Unknown LOG_FORMAT: {}", format));
+    }
+    let level = source.string("LOG_LEVEL", "info");
+    if let Err(error) = EnvFilter::builder().parse(&level) {
+        source.errors.push(format!("Invalid LOG_LEVEL {}: {}", level, error));
+    }
+    let redact = source
+        .string("LOG_REDACT", "")
+        .split(',')
+        .map(|field| field.trim().to_lowercase())
+        .filter(|field| !field.is_empty())
+        .collect();
+    let otlp_endpoint = source.value("OTEL_EXPORTER_OTLP_ENDPOINT", false);
+    if otlp_endpoint.is_some() && !cfg!(feature = "otlp") {
+        source.errors.push(String::from("OTEL_EXPORTER_OTLP_ENDPOINT requires the otlp feature"));
+    }
+    let service_name = source.string("OTEL_SERVICE_NAME", "dash_server");
+    #[cfg(not(feature = "otlp"))]
+    let _ = service_name;
+
+    LogConfig {
+        format,
+        level,
+        redact,
+        #[cfg(feature = "otlp")]
+        otlp_endpoint,
+        #[cfg(feature = "otlp")]
+        service_name,
+    }
+}
+
+fn load_metrics(source: &mut ConfigSource) -> MetricsConfig {
+    let enabled = source.parse("METRICS_ENABLED", true);
+    let host = source.string("METRICS_HOST", "127.0.0.1");
+    let port = source
+        .value("METRICS_PORT", false)
+        .and_then(|port| source.parse_value("METRICS_PORT", &port));
+    MetricsConfig { enabled, host, port }
+}
+
+fn load_server(source: &mut ConfigSource) -> ServerConfig {
+    let host = source.string("SERVER_HOST", "127.0.0.1");
+    let port = source.parse("SERVER_PORT", 3001);
+    let socket = source.value("SERVER_SOCKET", false).map(PathBuf::from);
+    if socket.is_some() && !cfg!(unix) {
+        source.errors.push(String::from("SERVER_SOCKET is only supported on Unix"));
+    }
+
+    let cert = source.value("TLS_CERT", false).map(PathBuf::from);
+    let key = source.value("TLS_KEY", false).map(PathBuf::from);
+    let reload_interval = Duration::from_secs(source.parse("TLS_RELOAD_INTERVAL", 60).max(1));
+    let tls = match (cert, key) {
+        (Some(cert), Some(key)) => Some(TlsConfig { cert, key, reload_interval }),
+        (None, None) => None,
+        _ => {
+            source.errors.push(String::from("TLS_CERT and TLS_KEY must be set together"));
+            None
+        }
+    };
+    if tls.is_some() && socket.is_some() {
+        source.errors.push(String::from("TLS cannot be used with SERVER_SOCKET"));
+    }
+
+    ServerConfig {
+        host,
+        port,
+        socket,
+        tls,
+        shutdown_delay: Duration::from_secs(source.parse("SHUTDOWN_DELAY", 0)),
+        shutdown_timeout: Duration::from_secs(source.parse("SHUTDOWN_TIMEOUT", 30)),
+    }
+}
+
+fn load_storage(source: &mut ConfigSource) -> StorageConfig {
+    let backend = source.string("STORAGE_BACKEND", "local");
+    let dir = PathBuf::from(source.string("STORAGE_DIR", "attachments"));
+    let s3_bucket = source.value("S3_BUCKET", false);
+    if !STORAGE_BACKENDS.contains(&backend.as_str()) {
+        source.errors.push(format!("Unknown STORAGE_BACKEND: {}", backend));
+    } else if backend == "s3" {
+        if !cfg!(feature = "s3") {
+            source.errors.push(String::from("STORAGE_BACKEND=s3 requires the s3 feature"));
+        }
+        if s3_bucket.is_none() {
+            source.errors.push(String::from("Missing S3_BUCKET for STORAGE_BACKEND=s3"));
+        }
+    }
+    StorageConfig { backend, dir, s3_bucket }
+}
+
+fn load_ws(source: &mut ConfigSource) -> WsConfig {
+    let ws = WsConfig {
+        channel_capacity: source.parse("WS_CHANNEL_CAPACITY", 100),
+        handshake_timeout: source.parse("WS_HANDSHAKE_TIMEOUT", 10),
+        history_size: source.parse("WS_HISTORY_SIZE", 100),
+        idle_timeout: source.parse("WS_IDLE_TIMEOUT", 90),
+        legacy_auth: source.parse("WS_LEGACY_AUTH", false),
+        max_sanction_duration: source.parse("WS_MAX_SANCTION_DURATION", 31536000),
+        outbound_queue: source.parse("WS_OUTBOUND_QUEUE", 64),
+        ping_interval: source.parse("WS_PING_INTERVAL", 30),
+        rate_limit: source.parse("WS_RATE_LIMIT", 10),
+        rate_window: source.parse("WS_RATE_WINDOW", 10),
+        ticket_expiry: source.parse("WS_TICKET_EXPIRY", 30),
+    };
+    for (key, invalid) in [
+        ("WS_CHANNEL_CAPACITY", ws.channel_capacity == 0),
+        ("WS_HANDSHAKE_TIMEOUT", ws.handshake_timeout == 0),
+        ("WS_HISTORY_SIZE", ws.history_size < 1),
+        ("WS_IDLE_TIMEOUT", ws.idle_timeout == 0),
+        ("WS_OUTBOUND_QUEUE", ws.outbound_queue == 0),
+        ("WS_PING_INTERVAL", ws.ping_interval == 0),
+        ("WS_RATE_WINDOW", ws.rate_window == 0),
+    ] {
+        if invalid {
+            source.errors.push(format!("{} must be greater than zero", key));
+        }
+    }
+    ws
+}
+
+fn load_source(args: &ConfigArgs) -> (Config, ConfigSource) {
+    let mut source = ConfigSource::new(args);
+    let auth = load_auth(&mut source);
+    let attachments = load_attachments(&mut source, &auth.jwt_secret);
+    let config = Config {
+        attachments,
+        auth,
+        backup: load_backup(&mut source),
+        database: load_database(&mut source),
+        log: load_log(&mut source),
+        metrics: load_metrics(&mut source),
+        server: load_server(&mut source),
+        storage: load_storage(&mut source),
+        ws: load_ws(&mut source),
+    };
+    source.check_unknown();
+    (config, source)
+}
+
+impl Config {
+    pub fn load(args: &ConfigArgs) -> Result<Self, Vec<String>> {
+        let (config, source) = load_source(args);
+        if source.errors.is_empty() { Ok(config) } else { Err(source.errors) }
+    }
+}
+
+pub fn print_errors(errors: &[String]) {
+    println!("Invalid configuration:");
+    for error in errors {
+        println!("  {}", error);
+    }
+}
+
+pub fn command(args: &ConfigArgs) -> Result<(), String> {
+    if args.command.len() != 2 || args.command[1] != "check" {
+        return Err(String::from(
+            "Usage: dash_server [--config <file>] [--key value]... config check",
+        ));
+    }
+
+    let (_, mut source) = load_source(args);
+    source.settings.sort_by(|a, b| a.key.cmp(&b.key));
+    for setting in &source.settings {
+        let value = match (&setting.value, setting.secret) {
+            (Some(_), true) => REDACTED,
+            (Some(value), false) => value.as_str(),
+            (None, _) => "",
+        };
+        println!("{:<28} {:<8} {}", setting.key, setting.origin.name(), value);
+    }
+    if source.errors.is_empty() {
+        println!("Configuration is valid");
+        Ok(())
+    } else {
+        print_errors(&source.errors);
+        Err(format!("Found {} configuration errors", source.errors.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use uuid::Uuid;
+
+    use super::{Config, parse_args};
+    use crate::test_utils::test_flags;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_splits_flags_from_command() {
+        let parsed =
+            parse_args(args(&["--config", "dash.toml", "--ws-rate-limit=5", "db", "backup", "x"]))
+                .unwrap();
+        assert_eq!(parsed.file.unwrap().to_str(), Some("dash.toml"));
+        assert_eq!(parsed.flags.get("WS_RATE_LIMIT").map(String::as_str), Some("5"));
+        assert_eq!(parsed.command, args(&["db", "backup", "x"]));
+
+        assert!(parse_args(args(&["--ws-rate-limit"])).is_err());
+    }
+
+    #[test]
+    fn flags_override_file_and_secrets_load_from_files() {
+        let directory = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&directory).unwrap();
+        let secret = directory.join("secret");
+        fs::write(&secret, "from-file\n").unwrap();
+        let file = directory.join("dash.toml");
+        fs::write(
+            &file,
+            format!(
+                "[ws]\nrate_limit = 5\nrate_window = 20\n\n[attachment]\nurl_secret_file = {:?}\n",
+                secret.to_str().unwrap()
+            ),
+        )
+        .unwrap();
+
+        let mut parsed = parse_args(args(&["--ws-rate-limit", "7"])).unwrap();
+        parsed.flags.extend(test_flags());
+        parsed.file = Some(file);
+        let config = Config::load(&parsed).unwrap();
+        assert_eq!(config.ws.rate_limit, 7);
+        assert_eq!(config.ws.rate_window, 20);
+        assert_eq!(config.attachments.url_secret, "from-file");
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn load_reports_all_errors() {
+        let directory = env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("dash.toml");
+        fs::write(
+            &file,
+            "ws_rate_limit = \"many\"\nws_ping_interval = 0\nws_history_size = -5\nunknown = 1\n",
+        )
+        .unwrap();
+
+        let mut parsed = parse_args(args(&[
+            "--storage-backend",
+            "ftp",
+            "--tls-cert",
+            "cert.pem",
+            "--no-such-flag",
+            "1",
+        ]))
+        .unwrap();
+        parsed.flags.extend(test_flags());
+        parsed.file = Some(file);
+        let errors = Config::load(&parsed).err().unwrap();
+        assert_eq!(
+            errors,
+            vec![
+                String::from("TLS_CERT and TLS_KEY must be set together"),
+                String::from("Unknown STORAGE_BACKEND: ftp"),
+                String::from("Cannot parse WS_RATE_LIMIT as u32: many"),
+                String::from("WS_HISTORY_SIZE must be greater than zero"),
+                String::from("WS_PING_INTERVAL must be greater than zero"),
+                String::from("Unknown flag: --no-such-flag"),
+                String::from("Unknown key in config file: unknown"),
+            ]
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use axum::body::Body;
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Router, middleware};
+use dash_types::attachment::AttachmentInfo;
+use dash_types::error::ApiErrorCode;
+use http::HeaderMap;
+use http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::error::{ApiError, DbError};
+use crate::extract::{Json, Multipart, Path, Query};
+use crate::middleware::auth_token::auth_token;
+use crate::state::AppState;
+use crate::strategies::attachment_strategy::{
+    can_access_attachment, get_attachment_by_id, invalid_file, store_attachment,
+    to_attachment_info, verify_signature,
+};
+use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
+use crate::strategies::realtime_strategy::get_active_user;
+
+const MULTIPART_OVERHEAD: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct DownloadParams {
+    expires: i64,
+    signature: String,
+    #[serde(default)]
+    thumbnail: bool,
+}
+
+async fn upload_attachment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Multipart(mut multipart): Multipart,
+) -> Result<(StatusCode, Json<AttachmentInfo>), ApiError> {
+    let claims = AuthRequestClaims::from_header(&headers);
+    let user = get_active_user(&state, &claims.sub).await?;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => return Err(ApiError::from_code(ApiErrorCode::MissingFields)),
+            Err(error) => {
+                return Err(invalid_file(&error.body_text()));
+            }
+        };
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = field.file_name().unwrap_or_default().to_string();
+        let content_type = field.content_type().unwrap_or_default().to_string();
+        let data = field.bytes().await.map_err(|error| invalid_file(&error.body_text()))?;
+
+        let attachment = store_attachment(
+            &state,
+            &user.uuid.to_string(),
+            &file_name,
+            &content_type,
+            data.to_vec(),
+        )
+        .await?;
+        let info = to_attachment_info(&state.config.attachments, attachment);
+        return Ok((StatusCode::CREATED, Json(info)));
+    }
+}
+
+async fn download_attachment(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<DownloadParams>,
+) -> Result<Response, ApiError> {
+    let config = &state.config.attachments;
+    if !verify_signature(config, &id, params.expires, params.thumbnail, &params.signature) {
+        return Err(ApiError::from_code(ApiErrorCode::AccessDenied));
+    }
+
+    let attachment = match get_attachment_by_id(&state.pool, &id).await.map_err(DbError::from) {
+        Ok(attachment) => attachment,
+        Err(DbError::NotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::AttachmentNotExist));
+        }
+        Err(error) => return Err(ApiError::from(error)),
+    };
+
+    let (key, content_type) = match (&attachment.thumbnail_key, params.thumbnail) {
+        (Some(thumbnail_key), true) => (thumbnail_key.as_str(), "image/png"),
+        (None, true) => return Err(ApiError::from_code(ApiErrorCode::AttachmentNotExist)),
+        (_, false) => (attachment.storage_key.as_str(), attachment.content_type.as_str()),
+    };
+    let data = match state.storage.get(key).await {
+        Ok(data) => data,
+        Err(error) => {
+            error!(%error, "Error reading attachment {} from storage", id);
+            return Err(ApiError::from_code(ApiErrorCode::ServerError));
+        }
+    };
+
+    let disposition = if content_type.starts_with("image/") { "inline" } else { "attachment" };
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(CONTENT_LENGTH, data.len())
+        .header(
+            CONTENT_DISPOSITION,
+            format!("{}; filename=\"{}\"", disposition, attachment.file_name),
+        )
+        .header(CACHE_CONTROL, "private, max-age=3600")
+        .header("X-Content-Type-Options", "nosniff")
+        .body(Body::from(data))
+        .unwrap())
+}
+
+async fn get_attachment_url(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    request: Request,
+) -> Result<(StatusCode, Json<AttachmentInfo>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let attachment = match get_attachment_by_id(&state.pool, &id).await.map_err(DbError::from) {
+        Ok(attachment) => attachment,
+        Err(DbError::NotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::AccessDenied));
+        }
+        Err(error) => return Err(ApiError::from(error)),
+    };
+
+    match can_access_attachment(&state.pool, &claims.sub, &attachment).await {
+        Ok(true) => {
+            Ok((StatusCode::OK, Json(to_attachment_info(&state.config.attachments, attachment))))
+        }
+        Ok(false) => Err(ApiError::from_code(ApiErrorCode::AccessDenied)),
+        Err(error) => Err(ApiError::from(error)),
+    }
+}
+
+pub fn routes(state: &AppState) -> Router<AppState> {
+    let request_token =
+        middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>);
+    let max_size = state.config.attachments.max_size + MULTIPART_OVERHEAD;
+    Router::new()
+        .route(
+            "/",
+            post(upload_attachment)
+                .layer(DefaultBodyLimit::max(max_size))
+                .layer(request_token.clone()),
+        )
+        .route("/{id}", get(download_attachment))
+        .route("/{id}/url", get(get_attachment_url).layer(request_token))
+}
@@ -1,35 +1,84 @@
-use axum::extract::Request;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
 use axum::http::StatusCode;
 use axum::routing::{get, post};
-use axum::{Json, Router, middleware};
+use axum::{Router, middleware};
 use bcrypt::verify;
-use dash_types::auth::{AuthErrorType, AuthToken};
+use dash_types::auth::AuthToken;
+use dash_types::error::{ApiErrorCode, FieldError, ProblemDetails};
+use dash_types::notification::NotificationCategory;
 use dash_types::user::{LoginUser, RegisterUser, UserInfo};
 use email_address::EmailAddress;
-use http::header::AUTHORIZATION;
+use http::header::{AUTHORIZATION, USER_AGENT};
 use http::{HeaderMap, HeaderValue};
+use tracing::{debug, error};
+use utoipa::OpenApi;
+use uuid::Uuid;
 
+use crate::config::Config;
+use crate::error::{ApiError, DbError};
+use crate::extract::Json;
 use crate::middleware::auth_token::auth_token;
-use crate::strategies::auth_strategy::{AuthClaims, AuthError, AuthRequestClaims, JWTClaims};
-use crate::strategies::user_strategy::{get_db_user_by_username_or_email, insert_db_user};
+use crate::repositories::user_repository::{NewUser, UserRepository};
+use crate::state::AppState;
+use crate::strategies::auth_strategy::{AuthClaims, AuthRequestClaims, JWTClaims};
+use crate::strategies::notification_strategy::{NewNotification, notify};
+use crate::strategies::user_strategy::hash_password;
 
-async fn test_auth_route(request: Request) -> Result<(StatusCode, String), AuthError> {
+#[utoipa::path(
+    get,
+    path = "/test",
+    tag = "auth",
+    security(("access_token" = [])),
+    responses(
+        (status = 200, description = "Access token is valid", body = String),
+        (status = 403, description = "Missing or invalid access token", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn test_auth_route(request: Request) -> Result<(StatusCode, String), ApiError> {
     let claims = AuthClaims::from_header(request.headers());
-    println!("Authentication claims: {:?}", claims);
+    debug!(user_uuid = %claims.sub, admin = claims.acc, "Authenticated test route");
     Ok((StatusCode::OK, "Authenticated".to_string()))
 }
 
-async fn request_with_token(request: Request) -> Result<(StatusCode, HeaderMap), AuthError> {
+#[utoipa::path(
+    get,
+    path = "/request",
+    tag = "auth",
+    security(("request_token" = [])),
+    responses(
+        (status = 201, description = "Access token issued",
+            headers(("Authorization" = String, description = "Access token"))),
+        (status = 403, description = "Invalid request token or disabled user", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "User no longer exists or server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn request_with_token(
+    State(users): State<Arc<dyn UserRepository>>,
+    State(config): State<Arc<Config>>,
+    request: Request,
+) -> Result<(StatusCode, HeaderMap), ApiError> {
     let claims = AuthRequestClaims::from_header(request.headers());
-    if let Ok(auth_claims) = AuthClaims::new(claims.sub.clone()).await {
-        let token_result = auth_claims.generate_token();
+    let auth_claims = match users.get_by_uuid(&claims.sub).await {
+        Ok(user) => AuthClaims::from_user(&user, &config.auth),
+        Err(DbError::NotFound) => return Err(ApiError::from_code(ApiErrorCode::TokenGeneration)),
+        Err(error) => return Err(ApiError::from(error)),
+    };
+    if let Ok(auth_claims) = auth_claims {
+        let token_result = auth_claims.generate_token(&config.auth);
         let auth_token: AuthToken;
         match token_result {
             Ok(token) => {
                 auth_token = token;
             }
             Err(error) => {
-                println!("Error generating token for UUID {}: {:?}", claims.sub, error);
+                error!(user_uuid = %claims.sub, ?error, "Error generating token");
                 return Err(error);
             }
         }
@@ -38,45 +87,76 @@ async fn request_with_token(request: Request) -> Result<(StatusCode, HeaderMap),
         header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
         Ok((StatusCode::CREATED, header_map.clone()))
     } else {
-        Err(AuthError::from_error_type(AuthErrorType::AccessDenied))
+        Err(ApiError::from_code(ApiErrorCode::AccessDenied))
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "auth",
+    request_body = RegisterUser,
+    responses(
+        (status = 201, description = "User registered", body = UserInfo,
+            headers(("Authorization" = String, description = "Request token"))),
+        (status = 400, description = "Invalid fields or malformed JSON", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 409, description = "Username or email already taken", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 422, description = "Body does not match the schema", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
 async fn register_user(
+    State(users): State<Arc<dyn UserRepository>>,
+    State(config): State<Arc<Config>>,
     Json(payload): Json<RegisterUser>,
-) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), AuthError> {
-    if payload.username.is_empty()
-        || payload.email.email().is_empty()
-        || payload.password.is_empty()
-    {
-        return Err(AuthError::from_error_type(AuthErrorType::MissingFields));
+) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), ApiError> {
+    let mut errors = Vec::new();
+    if payload.username.is_empty() {
+        errors.push(FieldError::new("username", "Username is required"));
     }
-
-    if !EmailAddress::is_valid(&payload.email.email()) {
-        return Err(AuthError::from_error_type(AuthErrorType::InvalidEmail));
+    if payload.email.email().is_empty() {
+        errors.push(FieldError::new("email", "Email is required"));
+    } else if !EmailAddress::is_valid(&payload.email.email()) {
+        errors.push(FieldError::new("email", "Email address is invalid"));
+    }
+    if payload.password.is_empty() {
+        errors.push(FieldError::new("password", "Password is required"));
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::validation(errors));
     }
 
-    let result = insert_db_user(payload).await;
-    if let Err(error) = result {
-        println!("Error creating user: {}", error);
-        if error.to_string().contains("duplicate key") {
-            return Err(AuthError::from_error_type(AuthErrorType::UserExists));
-        } else {
-            return Err(AuthError::from_error_type(AuthErrorType::ServerError));
+    let new_user = NewUser {
+        uuid: Uuid::new_v4(),
+        username: payload.username,
+        email: payload.email.to_string(),
+        password: hash_password(payload.password, config.auth.password_salt),
+        is_admin: false,
+    };
+    let user = match users.insert(new_user).await {
+        Ok(user) => user,
+        Err(DbError::UniqueViolation(_)) => {
+            return Err(ApiError::from_code(ApiErrorCode::UserExists));
         }
-    }
+        Err(error) => return Err(ApiError::from(error)),
+    };
 
-    let user = result.unwrap();
     let user_info = UserInfo::from_user(user);
-    let token_result =
-        AuthRequestClaims::new(user_info.uuid.clone()).await.unwrap().generate_token();
+    let token_result = AuthRequestClaims::new(user_info.uuid.to_string(), &config.auth)
+        .generate_token(&config.auth);
     let auth_token: AuthToken;
     match token_result {
         Ok(token) => {
             auth_token = token;
         }
         Err(error) => {
-            println!("Error generating token for UUID {}: {:?}", user_info.uuid, error);
+            error!(user_uuid = %user_info.uuid, ?error, "Error generating token");
             return Err(error);
         }
     }
@@ -86,54 +166,212 @@ async fn register_user(
     Ok((StatusCode::CREATED, header_map.clone(), Json(user_info)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "auth",
+    request_body = LoginUser,
+    responses(
+        (status = 200, description = "Signed in", body = UserInfo,
+            headers(("Authorization" = String, description = "Request token"))),
+        (status = 400, description = "Malformed JSON", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 403, description = "User is disabled", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 422, description = "Body does not match the schema", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
 async fn login_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
     Json(payload): Json<LoginUser>,
-) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), AuthError> {
+) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), ApiError> {
     if payload.username.is_empty() || payload.password.is_empty() {
-        return Err(AuthError::from_error_type(AuthErrorType::WrongCredentials));
+        return Err(ApiError::from_code(ApiErrorCode::WrongCredentials));
     }
 
-    let result = get_db_user_by_username_or_email(payload.username).await;
-    if let Err(_) = result {
-        return Err(AuthError::from_error_type(AuthErrorType::UserNotExist));
-    }
-
-    let user = result.unwrap();
+    let user = match state.users.get_by_username_or_email(&payload.username).await {
+        Ok(user) => user,
+        Err(DbError::NotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::UserNotExist));
+        }
+        Err(error) => return Err(ApiError::from(error)),
+    };
     if verify(payload.password, &user.password).unwrap() {
+        if user.is_disabled {
+            return Err(ApiError::from_code(ApiErrorCode::UserDisabled));
+        }
+
         let user_info = UserInfo::from_user(user);
+        let auth = &state.config.auth;
         let token_result =
-            AuthRequestClaims::new(user_info.uuid.clone()).await.unwrap().generate_token();
+            AuthRequestClaims::new(user_info.uuid.to_string(), auth).generate_token(auth);
         let auth_token: AuthToken;
         match token_result {
             Ok(token) => {
                 auth_token = token;
             }
             Err(error) => {
-                println!("Error generating token for UUID {}: {:?}", user_info.uuid, error);
+                error!(user_uuid = %user_info.uuid, ?error, "Error generating token");
                 return Err(error);
             }
         }
 
+        let user_agent = headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("an unknown device");
+        notify(
+            &state,
+            user_info.uuid.to_string(),
+            NewNotification::new(
+                NotificationCategory::Security,
+                "New sign-in",
+                format!("Your account was signed in from {}", user_agent),
+            ),
+        );
+
         let mut header_map = HeaderMap::new();
         header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
         Ok((StatusCode::OK, header_map.clone(), Json(user_info)))
     } else {
-        Err(AuthError::from_error_type(AuthErrorType::WrongCredentials))
+        Err(ApiError::from_code(ApiErrorCode::WrongCredentials))
     }
 }
 
-pub fn routes() -> Router {
+#[derive(OpenApi)]
+#[openapi(paths(test_auth_route, request_with_token, register_user, login_user))]
+pub struct AuthApi;
+
+pub fn routes(state: &AppState) -> Router<AppState> {
     Router::new()
         .merge(
             Router::new()
                 .route("/test", get(test_auth_route))
-                .layer(middleware::from_fn(auth_token::<AuthClaims>)),
+                .layer(middleware::from_fn_with_state(state.clone(), auth_token::<AuthClaims>)),
         )
         .merge(
-            Router::new()
-                .route("/request", get(request_with_token))
-                .layer(middleware::from_fn(auth_token::<AuthRequestClaims>)),
+            Router::new().route("/request", get(request_with_token)).layer(
+                middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>),
+            ),
         )
         .route("/register", post(register_user))
         .route("/login", post(login_user))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use http::Method;
+    use http::header::AUTHORIZATION;
+    use serde_json::json;
+
+    use super::routes;
+    use crate::repositories::user_repository::UserRepository;
+    use crate::repositories::user_repository::memory::InMemoryUserRepository;
+    use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
+    use crate::test_utils::{PASSWORD, create_user, send, test_config, test_state};
+
+    fn register_body(username: &str, email: &str) -> String {
+        json!({ "username": username, "email": email, "password": PASSWORD }).to_string()
+    }
+
+    #[tokio::test]
+    async fn register_creates_user() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let state = test_state(users.clone());
+        let router = routes(&state).with_state(state);
+
+        let body = register_body("alice", "alice@example.com");
+        let (status, headers, body) = send(&router, Method::POST, "/register", None, body).await;
+
+        assert_eq!(status, 201);
+        assert!(headers.contains_key(AUTHORIZATION));
+        assert_eq!(body["username"], "alice");
+        assert!(users.get_by_username("alice").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn register_rejects_duplicate_username_and_email() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        create_user(&users, "alice").await;
+        let state = test_state(users);
+        let router = routes(&state).with_state(state);
+
+        let body = register_body("alice", "other@example.com");
+        let (status, _, body) = send(&router, Method::POST, "/register", None, body).await;
+        assert_eq!(status, 409);
+        assert_eq!(body["code"], "user_exists");
+
+        let body = register_body("bob", "alice@example.com");
+        let (status, _, _) = send(&router, Method::POST, "/register", None, body).await;
+        assert_eq!(status, 409);
+    }
+
+    #[tokio::test]
+    async fn request_token_for_missing_user_is_token_generation_error() {
+        let state = test_state(Arc::default());
+        let router = routes(&state).with_state(state);
+
+        let auth = test_config().auth;
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let token = AuthRequestClaims::new(uuid, &auth).generate_token(&auth).unwrap().to_string();
+        let (status, _, body) = send(&router, Method::GET, "/request", Some(&token), "").await;
+        assert_eq!(status, 500);
+        assert_eq!(body["code"], "token_generation");
+    }
+
+    #[tokio::test]
+    async fn register_rejects_missing_fields() {
+        let state = test_state(Arc::default());
+        let router = routes(&state).with_state(state);
+
+        let body = json!({ "username": "", "email": "alice@example.com", "password": PASSWORD });
+        let (status, _, body) =
+            send(&router, Method::POST, "/register", None, body.to_string()).await;
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["errors"][0]["field"], "username");
+    }
+
+    #[tokio::test]
+    async fn login_rejects_wrong_password_and_unknown_user() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        create_user(&users, "alice").await;
+        let state = test_state(users);
+        let router = routes(&state).with_state(state);
+
+        let body = json!({ "username": "alice", "password": "wrong" }).to_string();
+        let (status, _, body) = send(&router, Method::POST, "/login", None, body).await;
+        assert_eq!(status, 401);
+        assert_eq!(body["code"], "wrong_credentials");
+
+        let body = json!({ "username": "bob", "password": PASSWORD }).to_string();
+        let (status, _, _) = send(&router, Method::POST, "/login", None, body).await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn login_rejects_disabled_user() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        users.set_disabled(&alice.uuid.to_string(), true).await.unwrap();
+        let state = test_state(users);
+        let router = routes(&state).with_state(state);
+
+        let body = json!({ "username": "alice@example.com", "password": PASSWORD }).to_string();
+        let (status, _, body) = send(&router, Method::POST, "/login", None, body).await;
+        assert_eq!(status, 403);
+        assert_eq!(body["code"], "user_disabled");
+    }
+}
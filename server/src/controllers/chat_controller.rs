@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{RequestExt, Router, middleware};
+use dash_types::chat::{ModerationAction, ReactionCount, ReadMarker, ReadMarkerUpdate, TypingUser};
+use dash_types::error::ApiErrorCode;
+use serde::Deserialize;
+
+use crate::error::{ApiError, DbError};
+use crate::extract::{Json, Path, Query};
+use crate::middleware::auth_token::auth_token;
+use crate::state::{AppState, ReadPool};
+use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
+use crate::strategies::chat_strategy::{
+    get_message_by_id, get_moderation_actions, get_reaction_counts, get_read_markers,
+};
+use crate::strategies::realtime_strategy::mark_read;
+use crate::strategies::typing_strategy::Typing;
+
+#[derive(Debug, Deserialize)]
+struct ModerationParams {
+    target: Option<String>,
+    limit: Option<i64>,
+}
+
+async fn get_moderation_log(
+    State(state): State<AppState>,
+    Query(params): Query<ModerationParams>,
+    request: Request,
+) -> Result<(StatusCode, Json<Vec<ModerationAction>>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    match state.users.get_by_uuid(&claims.sub).await {
+        Ok(user) if user.can_moderate() => {}
+        Ok(_) => return Err(ApiError::from_code(ApiErrorCode::AccessDenied)),
+        Err(DbError::NotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::UserNotExist));
+        }
+        Err(error) => return Err(ApiError::from(error)),
+    }
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    let actions =
+        get_moderation_actions(state.read_pool(), params.target.as_deref(), limit).await?;
+    Ok((StatusCode::OK, Json(actions)))
+}
+
+async fn get_room_typing(
+    State(typing): State<Arc<Typing>>,
+    Path(room): Path<String>,
+) -> (StatusCode, Json<Vec<TypingUser>>) {
+    (StatusCode::OK, Json(typing.get(&room)))
+}
+
+async fn get_room_read_markers(
+    State(ReadPool(pool)): State<ReadPool>,
+    Path(room): Path<String>,
+) -> Result<(StatusCode, Json<Vec<ReadMarker>>), ApiError> {
+    let markers = get_read_markers(&pool, &room).await?;
+    Ok((StatusCode::OK, Json(markers)))
+}
+
+async fn put_room_read_marker(
+    State(state): State<AppState>,
+    Path(room): Path<String>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let Json(update): Json<ReadMarkerUpdate> = request.extract().await?;
+
+    mark_read(&state, claims.sub, room, update.message_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_message_reactions(
+    State(ReadPool(pool)): State<ReadPool>,
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, Json<Vec<ReactionCount>>), ApiError> {
+    match get_message_by_id(&pool, id).await.map_err(DbError::from) {
+        Ok(_) => {}
+        Err(DbError::NotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::MessageNotExist));
+        }
+        Err(error) => return Err(ApiError::from(error)),
+    }
+
+    let reactions = get_reaction_counts(&pool, id).await?;
+    Ok((StatusCode::OK, Json(reactions)))
+}
+
+pub fn routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/moderation", get(get_moderation_log))
+        .route("/rooms/{room}/typing", get(get_room_typing))
+        .route("/rooms/{room}/read", get(get_room_read_markers).put(put_room_read_marker))
+        .route("/messages/{id}/reactions", get(get_message_reactions))
+        .layer(middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use dash_types::chat::{ChatMessage, SanctionKind};
+    use http::Method;
+
+    use super::routes;
+    use crate::repositories::user_repository::memory::InMemoryUserRepository;
+    use crate::state::AppState;
+    use crate::strategies::chat_strategy::{current_timestamp, insert_message, insert_sanction};
+    use crate::test_utils::{create_user, send, test_pool, test_state, token_for};
+
+    #[tokio::test]
+    async fn put_read_marker_validates_room_and_message() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        let state = AppState { pool: test_pool("sqlite::memory:").await, ..test_state(users) };
+        let message = ChatMessage {
+            id: 0,
+            room: String::from("general"),
+            user_uuid: alice.uuid.to_string(),
+            username: alice.username.clone(),
+            text: String::from("hello"),
+            created_at: current_timestamp(),
+            edited_at: None,
+            deleted_at: None,
+            attachments: Vec::new(),
+        };
+        let id = insert_message(&state.pool, &message).await.unwrap();
+        let uuid = alice.uuid.to_string();
+        insert_sanction(&state.pool, &uuid, Some("banned"), SanctionKind::Ban, None).await.unwrap();
+        let router = routes(&state).with_state(state);
+
+        let token = token_for(&alice);
+        let body = format!("{{\"message_id\":{}}}", id);
+        let (status, _, _) =
+            send(&router, Method::PUT, "/rooms/general/read", Some(&token), body.clone()).await;
+        assert_eq!(status, 204);
+
+        let (status, _, _) =
+            send(&router, Method::PUT, "/rooms/other/read", Some(&token), body.clone()).await;
+        assert_eq!(status, 404);
+
+        let (status, _, _) =
+            send(&router, Method::PUT, "/rooms/banned/read", Some(&token), body.clone()).await;
+        assert_eq!(status, 403);
+
+        let (status, _, _) =
+            send(&router, Method::PUT, "/rooms/bad.room/read", Some(&token), body).await;
+        assert_eq!(status, 400);
+    }
+}
@@ -0,0 +1,39 @@
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router, middleware};
+use dash_types::database::DatabaseStats;
+use dash_types::error::ApiErrorCode;
+
+use crate::error::ApiError;
+use crate::metrics::acquire_timeouts;
+use crate::middleware::auth_token::auth_token;
+use crate::pool::pool_stats;
+use crate::state::AppState;
+use crate::strategies::auth_strategy::{AuthClaims, JWTClaims};
+
+async fn get_stats(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<(StatusCode, Json<DatabaseStats>), ApiError> {
+    let claims = AuthClaims::from_header(request.headers());
+    if !claims.acc {
+        return Err(ApiError::from_code(ApiErrorCode::AccessDenied));
+    }
+
+    let stats = DatabaseStats {
+        backend: state.config.database.dialect.name().to_string(),
+        primary: pool_stats(&state.pool),
+        replica: state.replica.as_ref().map(pool_stats),
+        acquire_timeouts: acquire_timeouts(),
+    };
+    Ok((StatusCode::OK, Json(stats)))
+}
+
+pub fn routes(state: &AppState) -> Router<AppState> {
+    Router::new().route(
+        "/stats",
+        get(get_stats)
+            .layer(middleware::from_fn_with_state(state.clone(), auth_token::<AuthClaims>)),
+    )
+}
@@ -0,0 +1,66 @@
+use axum::Router;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::openapi::openapi;
+use crate::state::AppState;
+
+pub fn routes() -> Router<AppState> {
+    SwaggerUi::new("/docs").url("/openapi.json", openapi()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use axum::Router;
+    use http::{Method, StatusCode};
+    use serde_json::Value;
+
+    use super::routes;
+    use crate::controllers::{auth_controller, user_controller};
+    use crate::openapi::openapi;
+    use crate::test_utils::{send, test_state};
+
+    const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+    #[tokio::test]
+    async fn openapi_matches_committed_spec() {
+        let router = routes().with_state(test_state(Default::default()));
+        let (status, _, spec) = send(&router, Method::GET, "/openapi.json", None, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(spec["openapi"], "3.1.0");
+
+        if env::var_os("UPDATE_OPENAPI").is_some() {
+            fs::write(SPEC_PATH, serde_json::to_string_pretty(&spec).unwrap() + "\n").unwrap();
+        }
+        let committed: Value =
+            serde_json::from_str(&fs::read_to_string(SPEC_PATH).unwrap()).unwrap();
+        assert!(
+            spec == committed,
+            "server/openapi.json is out of date, regenerate it with UPDATE_OPENAPI=1 cargo test"
+        );
+    }
+
+    #[tokio::test]
+    async fn openapi_paths_are_routed() {
+        let state = test_state(Default::default());
+        let router = Router::new()
+            .nest("/auth", auth_controller::routes(&state))
+            .nest("/user", user_controller::routes(&state))
+            .with_state(state);
+
+        let spec = serde_json::to_value(openapi()).unwrap();
+        for (path, operations) in spec["paths"].as_object().unwrap() {
+            for method in operations.as_object().unwrap().keys() {
+                let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+                let (status, _, _) = send(&router, method.clone(), path, None, "").await;
+                assert!(
+                    status != StatusCode::NOT_FOUND && status != StatusCode::METHOD_NOT_ALLOWED,
+                    "{} {} is documented but not routed",
+                    method,
+                    path
+                );
+            }
+        }
+    }
+}
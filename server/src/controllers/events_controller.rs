@@ -0,0 +1,231 @@
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{RequestExt, Router, middleware};
+use dash_types::chat::{ChatMessage, DEFAULT_ROOM, NewChatMessage};
+use dash_types::error::ApiErrorCode;
+use dash_types::ws::WsServerMessage;
+use futures::stream::{self, Stream};
+use http::HeaderMap;
+use http::header::AUTHORIZATION;
+use jsonwebtoken::get_current_timestamp;
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Instant, sleep_until};
+use tracing::error;
+
+use crate::error::ApiError;
+use crate::extract::{Json, Query};
+use crate::middleware::auth_token::auth_token;
+use crate::state::AppState;
+use crate::strategies::attachment_strategy::load_attachments;
+use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
+use crate::strategies::chat_strategy::get_messages;
+use crate::strategies::realtime_strategy::{
+    Connection, Credential, apply_server_message, authenticate, enter_room, get_active_user,
+    join_room, leave_rooms, send_chat,
+};
+use crate::strategies::session_strategy::{Session, SessionCommand};
+
+const SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
+const SSE_LAST_EVENT_ID: &str = "Last-Event-ID";
+
+#[derive(Debug, Deserialize)]
+struct EventParams {
+    ticket: Option<String>,
+    rooms: Option<String>,
+    last_event_id: Option<i64>,
+}
+
+struct EventStream {
+    connection: Connection,
+    session: Session,
+    rx: broadcast::Receiver<WsServerMessage>,
+    backlog: Vec<WsServerMessage>,
+    resume_id: i64,
+    expiry: Instant,
+    is_closed: bool,
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        leave_rooms(&mut self.connection);
+    }
+}
+
+impl EventStream {
+    async fn next_message(&mut self) -> Option<WsServerMessage> {
+        if self.is_closed {
+            return None;
+        }
+        if let Some(message) = self.backlog.pop() {
+            return Some(message);
+        }
+
+        loop {
+            tokio::select! {
+                message = self.rx.recv() => {
+                    let message = match message {
+                        Ok(WsServerMessage::Chat(message)) if message.id <= self.resume_id => {
+                            continue;
+                        }
+                        Ok(message) => message,
+                        Err(RecvError::Lagged(missed)) => WsServerMessage::Lagged { missed },
+                        Err(RecvError::Closed) => return None,
+                    };
+
+                    let (is_delivered, close) = apply_server_message(&mut self.connection, &message);
+                    if let Some((_, reason)) = close {
+                        self.is_closed = true;
+                        return Some(WsServerMessage::Error { message: reason.to_string() });
+                    }
+                    if is_delivered {
+                        return Some(message);
+                    }
+                }
+                _ = sleep_until(self.expiry) => {
+                    self.is_closed = true;
+                    return Some(WsServerMessage::Error { message: String::from("Token expired") });
+                }
+                command = self.session.commands.recv() => {
+                    self.is_closed = true;
+                    let SessionCommand::Disconnect { reason, .. } = command?;
+                    return Some(WsServerMessage::Error { message: reason });
+                }
+            }
+        }
+    }
+}
+
+fn get_credential(headers: &HeaderMap, params: &EventParams) -> Option<Credential> {
+    if let Some(ticket) = &params.ticket {
+        return Some(Credential::Ticket(ticket.clone()));
+    }
+
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| Credential::Token(token.trim().to_string()))
+}
+
+fn get_last_event_id(headers: &HeaderMap, params: &EventParams) -> Option<i64> {
+    headers
+        .get(SSE_LAST_EVENT_ID)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(params.last_event_id)
+}
+
+fn to_event(message: &WsServerMessage) -> Result<Event, Infallible> {
+    let event = Event::default().data(serde_json::to_string(message).unwrap());
+    match message {
+        WsServerMessage::Chat(message) => Ok(event.id(message.id.to_string())),
+        _ => Ok(event),
+    }
+}
+
+async fn open_stream(
+    state: &AppState,
+    headers: &HeaderMap,
+    params: EventParams,
+) -> Result<impl Stream<Item = Result<Event, Infallible>> + use<>, ApiError> {
+    let credential =
+        get_credential(headers, &params).ok_or(ApiError::from_code(ApiErrorCode::Unauthorized))?;
+    let (user, exp) = authenticate(state, credential).await?;
+    let expiry = Instant::now() + Duration::from_secs(exp.saturating_sub(get_current_timestamp()));
+
+    let session = state.sessions.register(user.uuid.to_string());
+    let rx = state.pubsub.subscribe();
+    let mut connection = Connection::new(state.clone(), user);
+    let mut backlog = Vec::new();
+    let rooms = params.rooms.clone().unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    for room in rooms.split(',').map(str::trim).filter(|room| !room.is_empty()) {
+        if let Err(message) = join_room(&mut connection, room.to_string()).await {
+            backlog.push(WsServerMessage::Error { message });
+        }
+    }
+
+    let mut resume_id = 0;
+    if let Some(last_event_id) = get_last_event_id(headers, &params) {
+        let limit = state.config.ws.history_size;
+        let mut missed = Vec::new();
+        let mut is_truncated = false;
+        for room in &connection.rooms {
+            match get_messages(&state.pool, room, Some(last_event_id), limit + 1).await {
+                Ok(mut messages) => {
+                    if messages.len() as i64 > limit {
+                        messages.truncate(limit as usize);
+                        is_truncated = true;
+                    }
+                    if let Err(error) =
+                        load_attachments(&state.pool, &state.config.attachments, &mut messages)
+                            .await
+                    {
+                        error!(%error, "Error loading attachments for {}", room);
+                    }
+                    missed.extend(messages);
+                }
+                Err(error) => error!(%error, "Error getting missed events for {}", room),
+            }
+        }
+        missed.sort_by_key(|message| message.id);
+        resume_id = missed.last().map_or(last_event_id, |message| message.id);
+        backlog.extend(missed.into_iter().map(WsServerMessage::Chat));
+        if is_truncated {
+            backlog.push(WsServerMessage::Lagged { missed: 0 });
+        }
+    }
+    backlog.reverse();
+
+    let stream =
+        EventStream { connection, session, rx, backlog, resume_id, expiry, is_closed: false };
+    Ok(stream::unfold(stream, |mut stream| async move {
+        let message = stream.next_message().await?;
+        Some((to_event(&message), stream))
+    }))
+}
+
+async fn get_events(
+    State(state): State<AppState>,
+    Query(params): Query<EventParams>,
+    headers: HeaderMap,
+) -> Response {
+    match open_stream(&state, &headers, params).await {
+        Ok(stream) => (
+            [("X-Accel-Buffering", "no")],
+            Sse::new(stream).keep_alive(KeepAlive::new().interval(SSE_KEEP_ALIVE)),
+        )
+            .into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+async fn post_message(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<(StatusCode, Json<ChatMessage>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let Json(message): Json<NewChatMessage> = request.extract().await?;
+
+    let user = get_active_user(&state, &claims.sub).await?;
+    let mut connection = Connection::new(state, user);
+    let room = message.room.unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    enter_room(&mut connection, &room).await?;
+    let message = send_chat(&mut connection, Some(room), message.text, message.attachments).await?;
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+pub fn routes(state: &AppState) -> Router<AppState> {
+    Router::new().route("/", get(get_events)).route(
+        "/messages",
+        post(post_message)
+            .layer(middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>)),
+    )
+}
@@ -0,0 +1,68 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use dash_types::health::{HealthReport, HealthStatus};
+
+use crate::state::AppState;
+use crate::strategies::health_strategy::{liveness, readiness};
+
+async fn get_live() -> (StatusCode, Json<HealthReport>) {
+    (StatusCode::OK, Json(liveness()))
+}
+
+async fn get_ready(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let report = readiness(&state).await;
+    let status = match report.status {
+        HealthStatus::Pass => StatusCode::OK,
+        HealthStatus::Fail => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(report))
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/live", get(get_live)).route("/ready", get(get_ready))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+
+    use super::routes;
+    use crate::test_utils::{send, test_pool, test_state};
+
+    #[tokio::test]
+    async fn live_always_passes() {
+        let router = routes().with_state(test_state(Default::default()));
+
+        let (status, _, body) = send(&router, Method::GET, "/live", None, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], "pass");
+    }
+
+    #[tokio::test]
+    async fn ready_reports_each_check() {
+        let mut state = test_state(Default::default());
+        state.pool = test_pool("sqlite::memory:").await;
+        let router = routes().with_state(state);
+
+        let (status, _, body) = send(&router, Method::GET, "/ready", None, "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], "pass");
+        let names: Vec<_> = body["checks"].as_array().unwrap().iter().map(|c| &c["name"]).collect();
+        assert_eq!(names, ["config", "database", "migrations", "shutdown"]);
+        assert!(body["checks"][1]["latency_ms"].is_number());
+    }
+
+    #[tokio::test]
+    async fn ready_fails_without_migrations() {
+        let router = routes().with_state(test_state(Default::default()));
+
+        let (status, _, body) = send(&router, Method::GET, "/ready", None, "").await;
+        assert_eq!(status, 503);
+        assert_eq!(body["status"], "fail");
+        assert_eq!(body["checks"][1]["status"], "pass");
+        assert_eq!(body["checks"][2]["status"], "fail");
+        assert!(body["checks"][2]["error"].is_string());
+    }
+}
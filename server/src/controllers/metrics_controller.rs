@@ -0,0 +1,66 @@
+use axum::Router;
+use axum::extract::State;
+use axum::routing::get;
+use dash_types::error::ApiErrorCode;
+use http::HeaderName;
+use http::header::CONTENT_TYPE;
+use tracing::error;
+
+use crate::error::ApiError;
+use crate::metrics::render;
+use crate::state::AppState;
+
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+async fn get_metrics(
+    State(state): State<AppState>,
+) -> Result<([(HeaderName, &'static str); 1], String), ApiError> {
+    match render(&state) {
+        Ok(body) => Ok(([(CONTENT_TYPE, METRICS_CONTENT_TYPE)], body)),
+        Err(error) => {
+            error!(%error, "Error encoding metrics");
+            Err(ApiError::from_code(ApiErrorCode::ServerError))
+        }
+    }
+}
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/metrics", get(get_metrics))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::{Body, to_bytes};
+    use axum::routing::get;
+    use axum::{Router, middleware};
+    use http::{Method, Request};
+    use tower::ServiceExt;
+
+    use super::routes;
+    use crate::middleware::metrics::track_metrics;
+    use crate::test_utils::{send, test_state};
+
+    #[tokio::test]
+    async fn metrics_include_requests_by_route_and_pool_stats() {
+        let router = Router::new()
+            .route("/ping/{id}", get(|| async { "pong" }))
+            .merge(routes())
+            .route_layer(middleware::from_fn(track_metrics))
+            .with_state(test_state(Default::default()));
+
+        let (status, _, _) = send(&router, Method::GET, "/ping/1", None, "").await;
+        assert_eq!(status, 200);
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains(
+            r#"dash_http_requests_total{method="GET",route="/ping/{id}",status="200"} 1"#
+        ));
+        assert!(text.contains(r#"dash_db_pool_max_connections{pool="primary"} 1"#));
+        assert!(text.contains("dash_build_info{"));
+        assert!(text.contains("dash_ws_connections 0"));
+    }
+}
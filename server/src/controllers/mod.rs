@@ -1,3 +1,11 @@
+pub mod attachment_controller;
 pub mod auth_controller;
+pub mod chat_controller;
+pub mod database_controller;
+pub mod docs_controller;
+pub mod events_controller;
+pub mod health_controller;
+pub mod metrics_controller;
+pub mod notification_controller;
 pub mod user_controller;
 pub mod ws_controller;
@@ -0,0 +1,106 @@
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::routing::{delete, get, put};
+use axum::{RequestExt, Router, middleware};
+use dash_types::error::ApiErrorCode;
+use dash_types::notification::{NotificationList, NotificationPreference};
+use serde::Deserialize;
+
+use crate::error::ApiError;
+use crate::extract::{Json, Path, Query};
+use crate::middleware::auth_token::auth_token;
+use crate::pool::DbPool;
+use crate::state::AppState;
+use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
+use crate::strategies::notification_strategy::{
+    count_unread_notifications, delete_notification, get_notification_preferences,
+    get_notifications, mark_all_notifications_read, mark_notification_read,
+    set_notification_preference,
+};
+
+#[derive(Debug, Deserialize)]
+struct NotificationParams {
+    #[serde(default)]
+    unread: bool,
+    before: Option<i64>,
+    limit: Option<i64>,
+}
+
+async fn list_notifications(
+    State(pool): State<DbPool>,
+    Query(params): Query<NotificationParams>,
+    request: Request,
+) -> Result<(StatusCode, Json<NotificationList>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+    let notifications =
+        get_notifications(&pool, &claims.sub, params.unread, params.before, limit).await?;
+    let unread = count_unread_notifications(&pool, &claims.sub).await?;
+    Ok((StatusCode::OK, Json(NotificationList { notifications, unread })))
+}
+
+async fn read_all_notifications(
+    State(pool): State<DbPool>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    mark_all_notifications_read(&pool, &claims.sub).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn read_notification(
+    State(pool): State<DbPool>,
+    Path(id): Path<String>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    match mark_notification_read(&pool, &claims.sub, &id).await? {
+        result if result.rows_affected() > 0 => Ok(StatusCode::NO_CONTENT),
+        _ => Err(ApiError::from_code(ApiErrorCode::NotificationNotExist)),
+    }
+}
+
+async fn remove_notification(
+    State(pool): State<DbPool>,
+    Path(id): Path<String>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    match delete_notification(&pool, &claims.sub, &id).await? {
+        result if result.rows_affected() > 0 => Ok(StatusCode::NO_CONTENT),
+        _ => Err(ApiError::from_code(ApiErrorCode::NotificationNotExist)),
+    }
+}
+
+async fn get_preferences(
+    State(pool): State<DbPool>,
+    request: Request,
+) -> Result<(StatusCode, Json<Vec<NotificationPreference>>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let preferences = get_notification_preferences(&pool, &claims.sub).await?;
+    Ok((StatusCode::OK, Json(preferences)))
+}
+
+async fn put_preferences(
+    State(pool): State<DbPool>,
+    request: Request,
+) -> Result<(StatusCode, Json<Vec<NotificationPreference>>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let Json(preferences): Json<Vec<NotificationPreference>> = request.extract().await?;
+
+    for preference in preferences.iter() {
+        set_notification_preference(&pool, &claims.sub, preference).await?;
+    }
+    let preferences = get_notification_preferences(&pool, &claims.sub).await?;
+    Ok((StatusCode::OK, Json(preferences)))
+}
+
+pub fn routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_notifications))
+        .route("/read", put(read_all_notifications))
+        .route("/preferences", get(get_preferences).put(put_preferences))
+        .route("/{id}", delete(remove_notification))
+        .route("/{id}/read", put(read_notification))
+        .layer(middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>))
+}
@@ -1,63 +1,425 @@
-use axum::extract::{Json, Request};
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
 use axum::http::StatusCode;
-use axum::routing::{delete, get};
+use axum::routing::{delete, get, put};
 use axum::{RequestExt, Router, middleware};
-use dash_types::auth::AuthErrorType;
+use dash_types::error::{ApiErrorCode, FieldError, ProblemDetails};
+use dash_types::notification::NotificationCategory;
 use dash_types::user::UserInfo;
+use dash_types::ws::{CLOSE_USER_DISABLED, CLOSE_USER_REMOVED};
+use utoipa::OpenApi;
+use uuid::Uuid;
 
+use crate::error::{ApiError, DbError};
+use crate::extract::Json;
 use crate::middleware::auth_token::auth_token;
-use crate::strategies::auth_strategy::{AuthClaims, AuthError, AuthRequestClaims, JWTClaims};
-use crate::strategies::user_strategy::{delete_user_by_uuid, get_all_users, get_db_user_by_uuid};
+use crate::pubsub::PubSub;
+use crate::repositories::user_repository::UserRepository;
+use crate::state::AppState;
+use crate::strategies::auth_strategy::{AuthClaims, AuthRequestClaims, JWTClaims};
+use crate::strategies::notification_strategy::{NewNotification, notify};
+use crate::strategies::session_strategy::disconnect_user;
+
+async fn read_uuid(request: Request) -> Result<String, ApiError> {
+    let body: String = request.extract().await?;
+    let uuid = body.trim();
+    match Uuid::parse_str(uuid) {
+        Ok(_) => Ok(uuid.to_string()),
+        Err(_) => Err(ApiError::validation(vec![FieldError::new("uuid", "Must be a valid UUID")])),
+    }
+}
 
-async fn get_user_info(request: Request) -> Result<(StatusCode, Json<UserInfo>), AuthError> {
+#[utoipa::path(
+    get,
+    path = "/info",
+    tag = "user",
+    security(("request_token" = [])),
+    responses(
+        (status = 200, description = "Current user", body = UserInfo),
+        (status = 403, description = "Missing or invalid request token", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn get_user_info(
+    State(users): State<Arc<dyn UserRepository>>,
+    request: Request,
+) -> Result<(StatusCode, Json<UserInfo>), ApiError> {
     let claims = AuthRequestClaims::from_header(request.headers());
-    match get_db_user_by_uuid(claims.sub).await {
-        Ok(user) => Ok((StatusCode::OK, axum::Json(UserInfo::from_user(user)))),
-        Err(_) => Err(AuthError::from_error_type(AuthErrorType::UserNotExist)),
+    match users.get_by_uuid(&claims.sub).await {
+        Ok(user) => Ok((StatusCode::OK, Json(UserInfo::from_user(user)))),
+        Err(DbError::NotFound) => Err(ApiError::from_code(ApiErrorCode::UserNotExist)),
+        Err(error) => Err(ApiError::from(error)),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/all",
+    tag = "user",
+    security(("access_token" = [])),
+    responses(
+        (status = 200, description = "All users", body = Vec<UserInfo>),
+        (status = 403, description = "Invalid access token or not an admin", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
 async fn get_all_user_info(
+    State(users): State<Arc<dyn UserRepository>>,
     request: Request,
-) -> Result<(StatusCode, Json<Vec<UserInfo>>), AuthError> {
+) -> Result<(StatusCode, Json<Vec<UserInfo>>), ApiError> {
     let claims = AuthClaims::from_header(request.headers());
     if claims.acc {
-        match get_all_users().await {
-            Ok(users) => Ok((StatusCode::OK, axum::Json(users))),
-            Err(_) => Err(AuthError::from_error_type(AuthErrorType::UserNotExist)),
-        }
+        let users = users.get_all().await?;
+        Ok((StatusCode::OK, Json(users)))
     } else {
-        Err(AuthError::from_error_type(AuthErrorType::AccessDenied))
+        Err(ApiError::from_code(ApiErrorCode::AccessDenied))
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "",
+    tag = "user",
+    security(("access_token" = [])),
+    request_body(content = String, content_type = "text/plain", description = "User UUID"),
+    responses(
+        (status = 400, description = "Body is not a valid UUID", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 200, description = "User deleted"),
+        (status = 403, description = "Invalid access token or not an admin", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn delete_user(
+    State(users): State<Arc<dyn UserRepository>>,
+    State(pubsub): State<Arc<dyn PubSub>>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    let claims = AuthClaims::from_header(request.headers());
+    if !claims.acc {
+        return Err(ApiError::from_code(ApiErrorCode::AccessDenied));
+    }
+
+    let uuid = read_uuid(request).await?;
+    match users.delete(&uuid).await? {
+        0 => Err(ApiError::from_code(ApiErrorCode::UserNotExist)),
+        _ => {
+            disconnect_user(pubsub.as_ref(), &uuid, CLOSE_USER_REMOVED, "User removed");
+            Ok(StatusCode::OK)
+        }
     }
 }
 
-async fn delete_user(request: Request) -> Result<StatusCode, AuthError> {
+async fn set_user_disabled(
+    state: AppState,
+    request: Request,
+    is_disabled: bool,
+) -> Result<StatusCode, ApiError> {
     let claims = AuthClaims::from_header(request.headers());
-    let uuid = request.extract().await;
-    match uuid {
-        Ok(uuid) => {
-            if claims.acc {
-                match delete_user_by_uuid(uuid).await {
-                    Ok(_) => Ok(StatusCode::OK),
-                    Err(_) => Err(AuthError::from_error_type(AuthErrorType::UserNotExist)),
-                }
+    if !claims.acc {
+        return Err(ApiError::from_code(ApiErrorCode::AccessDenied));
+    }
+
+    let uuid = read_uuid(request).await?;
+
+    match state.users.set_disabled(&uuid, is_disabled).await? {
+        0 => Err(ApiError::from_code(ApiErrorCode::UserNotExist)),
+        _ => {
+            if is_disabled {
+                disconnect_user(state.pubsub.as_ref(), &uuid, CLOSE_USER_DISABLED, "User disabled");
             } else {
-                Err(AuthError::from_error_type(AuthErrorType::AccessDenied))
+                let body = String::from("Your account was enabled by an administrator");
+                let notification =
+                    NewNotification::new(NotificationCategory::Account, "Account enabled", body);
+                notify(&state, uuid, notification);
             }
+            Ok(StatusCode::OK)
         }
-        Err(error) => {
-            println!("{}", error);
-            Err(AuthError::from_error_type(AuthErrorType::ServerError))
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/disable",
+    tag = "user",
+    security(("access_token" = [])),
+    request_body(content = String, content_type = "text/plain", description = "User UUID"),
+    responses(
+        (status = 400, description = "Body is not a valid UUID", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 200, description = "User disabled"),
+        (status = 403, description = "Invalid access token or not an admin", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn disable_user(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    set_user_disabled(state, request, true).await
+}
+
+#[utoipa::path(
+    put,
+    path = "/enable",
+    tag = "user",
+    security(("access_token" = [])),
+    request_body(content = String, content_type = "text/plain", description = "User UUID"),
+    responses(
+        (status = 400, description = "Body is not a valid UUID", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 200, description = "User enabled"),
+        (status = 403, description = "Invalid access token or not an admin", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn enable_user(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    set_user_disabled(state, request, false).await
+}
+
+async fn set_user_moderator(
+    state: AppState,
+    request: Request,
+    is_moderator: bool,
+) -> Result<StatusCode, ApiError> {
+    let claims = AuthClaims::from_header(request.headers());
+    if !claims.acc {
+        return Err(ApiError::from_code(ApiErrorCode::AccessDenied));
+    }
+
+    let uuid = read_uuid(request).await?;
+
+    match state.users.set_moderator(&uuid, is_moderator).await? {
+        0 => Err(ApiError::from_code(ApiErrorCode::UserNotExist)),
+        _ => {
+            let (title, body) = if is_moderator {
+                ("Moderator access granted", "You were made a moderator by an administrator")
+            } else {
+                (
+                    "Moderator access revoked",
+                    "Your moderator access was revoked by an administrator",
+                )
+            };
+            let notification =
+                NewNotification::new(NotificationCategory::Account, title, body.to_string());
+            notify(&state, uuid, notification);
+            Ok(StatusCode::OK)
         }
     }
 }
 
-pub fn routes() -> Router {
+#[utoipa::path(
+    put,
+    path = "/moderator",
+    tag = "user",
+    security(("access_token" = [])),
+    request_body(content = String, content_type = "text/plain", description = "User UUID"),
+    responses(
+        (status = 400, description = "Body is not a valid UUID", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 200, description = "Moderator access granted"),
+        (status = 403, description = "Invalid access token or not an admin", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn grant_moderator(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    set_user_moderator(state, request, true).await
+}
+
+#[utoipa::path(
+    delete,
+    path = "/moderator",
+    tag = "user",
+    security(("access_token" = [])),
+    request_body(content = String, content_type = "text/plain", description = "User UUID"),
+    responses(
+        (status = 400, description = "Body is not a valid UUID", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 200, description = "Moderator access revoked"),
+        (status = 403, description = "Invalid access token or not an admin", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 404, description = "User does not exist", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 500, description = "Server error", body = ProblemDetails,
+            content_type = "application/problem+json"),
+        (status = 503, description = "Database unavailable", body = ProblemDetails,
+            content_type = "application/problem+json"),
+    )
+)]
+async fn revoke_moderator(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<StatusCode, ApiError> {
+    set_user_moderator(state, request, false).await
+}
+
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_user_info,
+    get_all_user_info,
+    delete_user,
+    disable_user,
+    enable_user,
+    grant_moderator,
+    revoke_moderator
+))]
+pub struct UserApi;
+
+pub fn routes(state: &AppState) -> Router<AppState> {
+    let request_token =
+        middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>);
+    let access_token = middleware::from_fn_with_state(state.clone(), auth_token::<AuthClaims>);
     Router::new()
-        .route(
-            "/info",
-            get(get_user_info).layer(middleware::from_fn(auth_token::<AuthRequestClaims>)),
-        )
-        .route("/all", get(get_all_user_info).layer(middleware::from_fn(auth_token::<AuthClaims>)))
-        .route("/", delete(delete_user).layer(middleware::from_fn(auth_token::<AuthClaims>)))
+        .route("/info", get(get_user_info).layer(request_token))
+        .route("/all", get(get_all_user_info).layer(access_token.clone()))
+        .route("/", delete(delete_user).layer(access_token.clone()))
+        .route("/disable", put(disable_user).layer(access_token.clone()))
+        .route("/enable", put(enable_user).layer(access_token.clone()))
+        .route("/moderator", put(grant_moderator).delete(revoke_moderator).layer(access_token))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use http::Method;
+
+    use super::routes;
+    use crate::repositories::user_repository::UserRepository;
+    use crate::repositories::user_repository::memory::InMemoryUserRepository;
+    use crate::test_utils::{create_user, send, test_state, token_for};
+
+    #[tokio::test]
+    async fn get_user_info_returns_current_user() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        let state = test_state(users);
+        let router = routes(&state).with_state(state);
+
+        let token = token_for(&alice);
+        let (status, _, body) = send(&router, Method::GET, "/info", Some(&token), "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["uuid"], alice.uuid.to_string());
+        assert_eq!(body["username"], "alice");
+    }
+
+    #[tokio::test]
+    async fn get_user_info_requires_token() {
+        let state = test_state(Arc::default());
+        let router = routes(&state).with_state(state);
+
+        let (status, _, _) = send(&router, Method::GET, "/info", None, "").await;
+        assert!(status.is_client_error());
+    }
+
+    #[tokio::test]
+    async fn get_all_user_info_requires_admin() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        let admin = create_user(&users, "admin").await;
+        users.set_admin(&admin.uuid.to_string(), true);
+        let admin = users.get_by_uuid(&admin.uuid.to_string()).await.unwrap();
+        let state = test_state(users);
+        let router = routes(&state).with_state(state);
+
+        let token = token_for(&alice);
+        let (status, _, _) = send(&router, Method::GET, "/all", Some(&token), "").await;
+        assert_eq!(status, 403);
+
+        let token = token_for(&admin);
+        let (status, _, body) = send(&router, Method::GET, "/all", Some(&token), "").await;
+        assert_eq!(status, 200);
+        assert_eq!(body.as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn delete_user_removes_user() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        let admin = create_user(&users, "admin").await;
+        users.set_admin(&admin.uuid.to_string(), true);
+        let admin = users.get_by_uuid(&admin.uuid.to_string()).await.unwrap();
+        let state = test_state(users.clone());
+        let router = routes(&state).with_state(state);
+
+        let token = token_for(&admin);
+        let uuid = alice.uuid.to_string();
+        let (status, _, _) = send(&router, Method::DELETE, "/", Some(&token), uuid.clone()).await;
+        assert_eq!(status, 200);
+        assert!(users.get_by_uuid(&alice.uuid.to_string()).await.is_err());
+
+        let (status, _, body) = send(&router, Method::DELETE, "/", Some(&token), uuid).await;
+        assert_eq!(status, 404);
+        assert_eq!(body["code"], "user_not_exist");
+
+        let (status, _, body) = send(&router, Method::DELETE, "/", Some(&token), "alice").await;
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["errors"][0]["field"], "uuid");
+    }
+
+    #[tokio::test]
+    async fn disable_user_requires_admin() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        let bob = create_user(&users, "bob").await;
+        let admin = create_user(&users, "admin").await;
+        users.set_admin(&admin.uuid.to_string(), true);
+        let admin = users.get_by_uuid(&admin.uuid.to_string()).await.unwrap();
+        let state = test_state(users.clone());
+        let router = routes(&state).with_state(state);
+
+        let token = token_for(&bob);
+        let uuid = alice.uuid.to_string();
+        let (status, _, _) =
+            send(&router, Method::PUT, "/disable", Some(&token), uuid.clone()).await;
+        assert_eq!(status, 403);
+        assert!(!users.get_by_uuid(&alice.uuid.to_string()).await.unwrap().is_disabled);
+
+        let token = token_for(&admin);
+        let (status, _, _) = send(&router, Method::PUT, "/disable", Some(&token), uuid).await;
+        assert_eq!(status, 200);
+        assert!(users.get_by_uuid(&alice.uuid.to_string()).await.unwrap().is_disabled);
+    }
 }
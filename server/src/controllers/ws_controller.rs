@@ -1,81 +1,423 @@
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
-
-use axum::Router;
-use axum::extract::State;
-use axum::extract::ws::{Message, Utf8Bytes, WebSocket, WebSocketUpgrade};
-use axum::response::IntoResponse;
-use axum::routing::get;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router, middleware};
+use dash_types::chat::DEFAULT_ROOM;
+use dash_types::error::ApiErrorCode;
+use dash_types::user::User;
+use dash_types::ws::{
+    CLOSE_IDLE_TIMEOUT, CLOSE_SLOW_CONSUMER, CLOSE_TOKEN_EXPIRED, WsClientMessage, WsServerMessage,
+    WsStats, WsTicket,
+};
 use futures::sink::SinkExt;
 use futures::stream::StreamExt;
-use tokio::sync::broadcast;
+use http::HeaderMap;
+use http::header::{AUTHORIZATION, SEC_WEBSOCKET_PROTOCOL};
+use jsonwebtoken::get_current_timestamp;
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{Instant, interval_at, sleep_until, timeout};
+use tracing::{Instrument, info_span, warn};
+
+use crate::error::ApiError;
+use crate::extract::Query;
+use crate::metrics::{record_ws_dropped, record_ws_lag};
+use crate::middleware::auth_token::auth_token;
+use crate::state::AppState;
+use crate::strategies::auth_strategy::{AuthClaims, AuthRequestClaims, JWTClaims};
+use crate::strategies::realtime_strategy::{
+    Connection, Credential, apply_server_message, authenticate, get_active_user,
+    handle_client_message, issue_ticket, join_room, leave_rooms, reauthenticate,
+};
+use crate::strategies::session_strategy::SessionCommand;
 
-use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
-use crate::strategies::user_strategy::get_db_user_by_uuid;
+const WS_PROTOCOL: &str = "dash";
+const WS_BEARER_PROTOCOL_PREFIX: &str = "bearer.";
+const WS_CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
 
-struct AppState {
-    user_set: Mutex<HashSet<String>>,
-    tx: broadcast::Sender<Utf8Bytes>,
+#[derive(Default)]
+struct WsMetrics {
+    connections: AtomicU64,
+    lagged_clients: AtomicU64,
+    missed_messages: AtomicU64,
+    dropped_clients: AtomicU64,
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
-    let (mut sender, mut receiver) = socket.split();
-    let mut username = String::new();
-    while let Some(Ok(auth)) = receiver.next().await {
-        if let Message::Text(text) = auth {
-            if let Ok(claim) = AuthRequestClaims::from_string(&text) {
-                username = get_db_user_by_uuid(claim.sub.clone()).await.unwrap().username;
-                break;
-            } else {
-                sender.close().await.unwrap();
-                return;
+impl WsMetrics {
+    fn stats(&self) -> WsStats {
+        WsStats {
+            connections: self.connections.load(Ordering::Relaxed),
+            lagged_clients: self.lagged_clients.load(Ordering::Relaxed),
+            missed_messages: self.missed_messages.load(Ordering::Relaxed),
+            dropped_clients: self.dropped_clients.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WsState {
+    metrics: WsMetrics,
+}
+
+impl WsState {
+    pub fn stats(&self) -> WsStats {
+        self.metrics.stats()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WsParams {
+    ticket: Option<String>,
+}
+
+fn offered_protocols(headers: &HeaderMap) -> Vec<&str> {
+    headers
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect()
+}
+
+fn get_credential(headers: &HeaderMap, params: &WsParams) -> Result<Option<Credential>, ApiError> {
+    if let Some(ticket) = &params.ticket {
+        return Ok(Some(Credential::Ticket(ticket.clone())));
+    }
+
+    if let Some(token) = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Ok(Some(Credential::Token(token.trim().to_string())));
+    }
+
+    let protocols = offered_protocols(headers);
+    let Some(token) =
+        protocols.iter().find_map(|protocol| protocol.strip_prefix(WS_BEARER_PROTOCOL_PREFIX))
+    else {
+        return Ok(None);
+    };
+    if !protocols.contains(&WS_PROTOCOL) {
+        return Err(ApiError::with_detail(
+            ApiErrorCode::Unauthorized,
+            format!(
+                "Offer the {} subprotocol alongside {}<token>",
+                WS_PROTOCOL, WS_BEARER_PROTOCOL_PREFIX
+            ),
+        ));
+    }
+    Ok(Some(Credential::Token(token.to_string())))
+}
+
+fn expiry_instant(exp: u64) -> Instant {
+    Instant::now() + Duration::from_secs(exp.saturating_sub(get_current_timestamp()))
+}
+
+async fn authenticate_first_frame(state: &AppState, socket: &mut WebSocket) -> Option<(User, u64)> {
+    let deadline = Duration::from_secs(state.config.ws.handshake_timeout);
+    let first_frame = timeout(deadline, async {
+        while let Some(Ok(message)) = socket.recv().await {
+            if let Message::Text(text) = message {
+                return Some(text);
             }
         }
+        None
+    });
+
+    let token = match first_frame.await {
+        Ok(Some(token)) => token,
+        Ok(None) => return None,
+        Err(_) => {
+            warn!("WebSocket handshake timed out after {} seconds", deadline.as_secs());
+            return None;
+        }
+    };
+
+    let claims = AuthRequestClaims::from_string(&token, &state.config.auth).ok()?;
+    match get_active_user(state, &claims.sub).await {
+        Ok(user) => Some((user, claims.exp)),
+        Err(error) => {
+            warn!(user_uuid = %claims.sub, ?error, "Error authenticating WebSocket user");
+            None
+        }
     }
+}
 
-    let mut rx = state.tx.subscribe();
-    let msg = format!("{} joined", username).into();
-    let _ = state.tx.send(msg);
-    let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            if msg == "" {
-                break;
+fn close_frame(code: u16, reason: &str) -> CloseFrame {
+    CloseFrame { code, reason: reason.into() }
+}
+
+fn enqueue(
+    state: &WsState,
+    outbound: &mpsc::Sender<Message>,
+    message: Message,
+) -> Result<(), Option<CloseFrame>> {
+    match outbound.try_send(message) {
+        Ok(()) => Ok(()),
+        Err(TrySendError::Full(_)) => {
+            state.metrics.dropped_clients.fetch_add(1, Ordering::Relaxed);
+            record_ws_dropped();
+            Err(Some(close_frame(CLOSE_SLOW_CONSUMER, "Slow consumer")))
+        }
+        Err(TrySendError::Closed(_)) => Err(None),
+    }
+}
+
+fn enqueue_message(
+    state: &WsState,
+    outbound: &mpsc::Sender<Message>,
+    message: &WsServerMessage,
+) -> Result<(), Option<CloseFrame>> {
+    let text = serde_json::to_string(message).unwrap();
+    enqueue(state, outbound, Message::Text(text.into()))
+}
+
+async fn handle_socket(mut socket: WebSocket, app_state: AppState, auth: Option<(User, u64)>) {
+    let (user, exp) = match auth {
+        Some(auth) => auth,
+        None => match authenticate_first_frame(&app_state, &mut socket).await {
+            Some(auth) => auth,
+            None => {
+                let _ = socket.close().await;
+                return;
             }
-            if sender.send(Message::Text(msg)).await.is_err() {
+        },
+    };
+
+    let state = app_state.ws.clone();
+    let mut session = app_state.sessions.register(user.uuid.to_string());
+    let mut connection = Connection::new(app_state.clone(), user);
+
+    let (mut sender, mut receiver) = socket.split();
+    let (outbound, mut outbound_rx) = mpsc::channel::<Message>(app_state.config.ws.outbound_queue);
+    let mut writer = tokio::spawn(async move {
+        while let Some(message) = outbound_rx.recv().await {
+            if sender.send(message).await.is_err() {
                 break;
             }
         }
+        let _ = sender.close().await;
     });
 
-    let tx = state.tx.clone();
-    let name = username.clone();
-    let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(Message::Text(text))) = receiver.next().await {
-            if text == "" {
-                break;
+    state.metrics.connections.fetch_add(1, Ordering::Relaxed);
+    let mut rx = app_state.pubsub.subscribe();
+    if let Err(message) = join_room(&mut connection, DEFAULT_ROOM.to_string()).await {
+        let _ = enqueue_message(&state, &outbound, &WsServerMessage::Error { message });
+    }
+
+    let ping_period = Duration::from_secs(app_state.config.ws.ping_interval);
+    let idle_timeout = Duration::from_secs(app_state.config.ws.idle_timeout);
+    let mut ping_interval = interval_at(Instant::now() + ping_period, ping_period);
+    let mut last_seen = Instant::now();
+    let token_expiry = sleep_until(expiry_instant(exp));
+    tokio::pin!(token_expiry);
+
+    let close = loop {
+        tokio::select! {
+            message = receiver.next() => {
+                let Some(Ok(message)) = message else {
+                    break None;
+                };
+                last_seen = Instant::now();
+
+                let text = match message {
+                    Message::Text(text) => text,
+                    Message::Close(_) => break None,
+                    _ => continue,
+                };
+                if text.is_empty() {
+                    break None;
+                }
+
+                let client_message = serde_json::from_str(&text)
+                    .unwrap_or(WsClientMessage::Chat {
+                        room: None,
+                        text: text.to_string(),
+                        attachments: Vec::new(),
+                    });
+                let reply = match client_message {
+                    WsClientMessage::Auth { token } => {
+                        match reauthenticate(&app_state, &connection.user.uuid.to_string(), &token).await {
+                            Ok(exp) => {
+                                token_expiry.as_mut().reset(expiry_instant(exp));
+                                Some(WsServerMessage::AuthExtended { exp })
+                            }
+                            Err(error) => {
+                                Some(WsServerMessage::Error { message: error.detail() })
+                            }
+                        }
+                    }
+                    client_message => {
+                        match handle_client_message(&mut connection, client_message).await {
+                            Ok(reply) => reply,
+                            Err(message) => Some(WsServerMessage::Error { message }),
+                        }
+                    }
+                };
+                if let Some(reply) = reply {
+                    if let Err(close) = enqueue_message(&state, &outbound, &reply) {
+                        break close;
+                    }
+                }
+            }
+            message = rx.recv() => {
+                let message = match message {
+                    Ok(message) => message,
+                    Err(RecvError::Lagged(missed)) => {
+                        state.metrics.lagged_clients.fetch_add(1, Ordering::Relaxed);
+                        state.metrics.missed_messages.fetch_add(missed, Ordering::Relaxed);
+                        record_ws_lag(missed);
+                        warn!(username = %connection.user.username, missed, "WebSocket client lagged");
+                        WsServerMessage::Lagged { missed }
+                    }
+                    Err(RecvError::Closed) => break None,
+                };
+
+                let (is_delivered, close) = apply_server_message(&mut connection, &message);
+                let close = close.map(|(code, reason)| close_frame(code, reason));
+                if is_delivered {
+                    if let Err(close) = enqueue_message(&state, &outbound, &message) {
+                        break close;
+                    }
+                }
+                if close.is_some() {
+                    break close;
+                }
             }
-            let _ = tx.send(format!("{}: {}", name, text).into());
+            _ = ping_interval.tick() => {
+                if last_seen.elapsed() > idle_timeout {
+                    break Some(close_frame(CLOSE_IDLE_TIMEOUT, "Idle timeout"));
+                }
+                if let Err(close) = enqueue(&state, &outbound, Message::Ping(Default::default())) {
+                    break close;
+                }
+            }
+            _ = &mut token_expiry => {
+                break Some(close_frame(CLOSE_TOKEN_EXPIRED, "Token expired"));
+            }
+            command = session.commands.recv() => match command {
+                Some(SessionCommand::Disconnect { code, reason }) => {
+                    break Some(close_frame(code, &reason));
+                }
+                None => break None,
+            },
         }
-    });
+    };
 
-    tokio::select! {
-        _ = (&mut send_task) => recv_task.abort(),
-        _ = (&mut recv_task) => send_task.abort(),
+    if let Some(frame) = close {
+        let _ = timeout(WS_CLOSE_TIMEOUT, outbound.send(Message::Close(Some(frame)))).await;
+    }
+    drop(outbound);
+    if timeout(WS_CLOSE_TIMEOUT, &mut writer).await.is_err() {
+        writer.abort();
     }
+    state.metrics.connections.fetch_sub(1, Ordering::Relaxed);
 
-    let msg = format!("{} left", username).into();
-    let _ = state.tx.send(msg);
+    leave_rooms(&mut connection);
+}
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(params): Query<WsParams>,
+    headers: HeaderMap,
+) -> Response {
+    let auth = match get_credential(&headers, &params) {
+        Ok(Some(credential)) => match authenticate(&state, credential).await {
+            Ok(auth) => Some(auth),
+            Err(error) => return error.into_response(),
+        },
+        Ok(None) if state.config.ws.legacy_auth => None,
+        Ok(None) => return ApiError::from_code(ApiErrorCode::Unauthorized).into_response(),
+        Err(error) => return error.into_response(),
+    };
+
+    let span = info_span!("websocket");
+    ws.protocols([WS_PROTOCOL])
+        .on_upgrade(|socket| handle_socket(socket, state, auth).instrument(span))
+}
+
+async fn create_ticket(
+    State(state): State<AppState>,
+    request: Request,
+) -> Result<(StatusCode, Json<WsTicket>), ApiError> {
+    let claims = AuthRequestClaims::from_header(request.headers());
+    let ticket = issue_ticket(&state, &claims.sub, claims.exp).await?;
+    let expires_in = state.config.ws.ticket_expiry;
+    Ok((StatusCode::CREATED, Json(WsTicket { ticket, expires_in })))
+}
 
-    state.user_set.lock().unwrap().remove(&username);
+async fn get_stats(
+    State(state): State<Arc<WsState>>,
+    request: Request,
+) -> Result<(StatusCode, Json<WsStats>), ApiError> {
+    let claims = AuthClaims::from_header(request.headers());
+    if claims.acc {
+        Ok((StatusCode::OK, Json(state.stats())))
+    } else {
+        Err(ApiError::from_code(ApiErrorCode::AccessDenied))
+    }
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+pub fn routes(state: &AppState) -> Router<AppState> {
+    let request_token =
+        middleware::from_fn_with_state(state.clone(), auth_token::<AuthRequestClaims>);
+    let access_token = middleware::from_fn_with_state(state.clone(), auth_token::<AuthClaims>);
+    Router::new()
+        .route("/", get(ws_handler))
+        .route("/stats", get(get_stats).layer(access_token))
+        .route("/ticket", post(create_ticket).layer(request_token))
 }
 
-pub fn routes() -> Router {
-    let user_set = Mutex::new(HashSet::new());
-    let (tx, _) = broadcast::channel(100);
-    let app_state = Arc::new(AppState { user_set, tx });
-    Router::new().route("/", get(ws_handler)).with_state(app_state)
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+    use crate::repositories::user_repository::memory::InMemoryUserRepository;
+    use crate::test_utils::{create_user, test_pool, test_state};
+
+    fn protocol_headers(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn bearer_protocol_requires_dash() {
+        let params = WsParams { ticket: None };
+
+        let credential = get_credential(&protocol_headers("dash, bearer.abc"), &params).unwrap();
+        assert!(matches!(credential, Some(Credential::Token(token)) if token == "abc"));
+
+        let error = get_credential(&protocol_headers("bearer.abc"), &params).err().unwrap();
+        assert_eq!(error.code(), ApiErrorCode::Unauthorized);
+
+        assert!(get_credential(&protocol_headers("dash"), &params).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn tickets_are_single_use() {
+        let users = Arc::new(InMemoryUserRepository::default());
+        let alice = create_user(&users, "alice").await;
+        let state = AppState { pool: test_pool("sqlite::memory:").await, ..test_state(users) };
+
+        let ticket = issue_ticket(&state, &alice.uuid.to_string(), 1).await.unwrap();
+        let (user, exp) = authenticate(&state, Credential::Ticket(ticket.clone())).await.unwrap();
+        assert_eq!(user.uuid, alice.uuid);
+        assert_eq!(exp, 1);
+
+        let error = authenticate(&state, Credential::Ticket(ticket)).await.err().unwrap();
+        assert_eq!(error.code(), ApiErrorCode::Unauthorized);
+    }
 }
@@ -0,0 +1,230 @@
+use std::ffi::{CStr, c_int};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use libsqlite3_sys as ffi;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Connection};
+use tokio::time::{Instant, interval_at};
+use tracing::{error, info, warn};
+
+use crate::config::{BackupConfig, Config};
+use crate::dialect::Dialect;
+use crate::pool::{self, DatabaseConfig, DbPool};
+use crate::strategies::chat_strategy::current_timestamp;
+
+const BACKUP_PREFIX: &str = "dash-";
+const BACKUP_EXTENSION: &str = "sqlite";
+
+fn check_sqlite(database: &DatabaseConfig) -> Result<(), String> {
+    if database.dialect != Dialect::Sqlite {
+        return Err(String::from(
+            "Backups are only supported for SQLite, use pg_dump for PostgreSQL",
+        ));
+    }
+    if database.is_in_memory() {
+        return Err(String::from("Cannot back up an in-memory SQLite database"));
+    }
+    Ok(())
+}
+
+async fn open_sqlite(
+    database: &DatabaseConfig,
+    options: SqliteConnectOptions,
+) -> Result<SqliteConnection, String> {
+    options
+        .busy_timeout(database.sqlite_busy_timeout)
+        .connect()
+        .await
+        .map_err(|error| format!("Could not open SQLite database: {}", error))
+}
+
+fn sqlite_error(code: c_int) -> String {
+    let message = unsafe { CStr::from_ptr(ffi::sqlite3_errstr(code)) };
+    message.to_string_lossy().into_owned()
+}
+
+async fn copy_database(
+    source: &mut SqliteConnection,
+    destination: &mut SqliteConnection,
+) -> Result<(), String> {
+    let mut source = source.lock_handle().await.map_err(|error| error.to_string())?;
+    let mut destination = destination.lock_handle().await.map_err(|error| error.to_string())?;
+    let source = source.as_raw_handle().as_ptr();
+    let destination = destination.as_raw_handle().as_ptr();
+
+    let main = c"main";
+    let code = unsafe {
+        let backup = ffi::sqlite3_backup_init(destination, main.as_ptr(), source, main.as_ptr());
+        if backup.is_null() {
+            return Err(sqlite_error(ffi::sqlite3_errcode(destination)));
+        }
+        let code = ffi::sqlite3_backup_step(backup, -1);
+        ffi::sqlite3_backup_finish(backup);
+        code
+    };
+    match code {
+        ffi::SQLITE_DONE => Ok(()),
+        code => Err(sqlite_error(code)),
+    }
+}
+
+pub async fn backup(database: &DatabaseConfig, path: &Path) -> Result<(), String> {
+    check_sqlite(database)?;
+    if path.exists() {
+        return Err(format!("Backup file already exists: {}", path.display()));
+    }
+
+    let partial = path.with_extension("partial");
+    let source_options = SqliteConnectOptions::from_str(&database.url)
+        .map_err(|error| error.to_string())?
+        .read_only(true);
+    let mut source = open_sqlite(database, source_options).await?;
+    let destination_options =
+        SqliteConnectOptions::new().filename(&partial).create_if_missing(true);
+    let mut destination = open_sqlite(database, destination_options).await?;
+
+    let result = copy_database(&mut source, &mut destination).await;
+    let _ = source.close().await;
+    let _ = destination.close().await;
+    match result {
+        Ok(()) => fs::rename(&partial, path).map_err(|error| error.to_string()),
+        Err(error) => {
+            let _ = fs::remove_file(&partial);
+            Err(format!("Could not back up database: {}", error))
+        }
+    }
+}
+
+async fn check_integrity(database: &DatabaseConfig, path: &Path) -> Result<(), String> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let mut connection = open_sqlite(database, options).await?;
+    let result: Result<String, sqlx::Error> =
+        sqlx::query_scalar("PRAGMA integrity_check;").fetch_one(&mut connection).await;
+    let _ = connection.close().await;
+    match result {
+        Ok(result) if result == "ok" => Ok(()),
+        Ok(result) => Err(format!("Backup failed integrity check: {}", result)),
+        Err(error) => Err(format!("Could not check backup integrity: {}", error)),
+    }
+}
+
+pub async fn restore(database: &DatabaseConfig, path: &Path) -> Result<(), String> {
+    check_sqlite(database)?;
+    if !path.is_file() {
+        return Err(format!("Backup file does not exist: {}", path.display()));
+    }
+    check_integrity(database, path).await?;
+
+    let source_options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let mut source = open_sqlite(database, source_options).await?;
+    let destination_options = SqliteConnectOptions::from_str(&database.url)
+        .map_err(|error| error.to_string())?
+        .create_if_missing(true);
+    let mut destination = open_sqlite(database, destination_options).await?;
+
+    let result = copy_database(&mut source, &mut destination).await;
+    let _ = source.close().await;
+    let _ = destination.close().await;
+    result.map_err(|error| format!("Could not restore database: {}", error))
+}
+
+fn list_backups(directory: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut backups: Vec<(i64, PathBuf)> = fs::read_dir(directory)
+        .map_err(|error| error.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            if path.extension()?.to_str()? != BACKUP_EXTENSION {
+                return None;
+            }
+            let timestamp =
+                path.file_stem()?.to_str()?.strip_prefix(BACKUP_PREFIX)?.parse().ok()?;
+            Some((timestamp, path))
+        })
+        .collect();
+    backups.sort();
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+async fn scheduled_backup(
+    database: &DatabaseConfig,
+    directory: &Path,
+    retention: usize,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(directory).map_err(|error| error.to_string())?;
+    let file_name = format!("{}{}.{}", BACKUP_PREFIX, current_timestamp(), BACKUP_EXTENSION);
+    let path = directory.join(file_name);
+    backup(database, &path).await?;
+
+    let backups = list_backups(directory)?;
+    let expired = backups.len().saturating_sub(retention);
+    for path in &backups[..expired] {
+        if let Err(error) = fs::remove_file(path) {
+            warn!(%error, "Error removing expired backup {}", path.display());
+        }
+    }
+    Ok(path)
+}
+
+pub fn schedule_backups(database: &DatabaseConfig, backup: &BackupConfig) {
+    let Some(directory) = backup.dir.clone() else {
+        return;
+    };
+    if let Err(error) = check_sqlite(database) {
+        warn!("Scheduled backups disabled: {}", error);
+        return;
+    }
+
+    let database = database.clone();
+    let period = Duration::from_secs(backup.interval);
+    let retention = backup.retention;
+    info!(
+        "Backing up database to {} every {} seconds, keeping {}",
+        directory.display(),
+        period.as_secs(),
+        retention
+    );
+    tokio::spawn(async move {
+        let mut interval = interval_at(Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            match scheduled_backup(&database, &directory, retention).await {
+                Ok(path) => info!("Database backed up to {}", path.display()),
+                Err(error) => error!(%error, "Error backing up database"),
+            }
+        }
+    });
+}
+
+async fn maintain(pool: &DbPool, statement: &str) -> Result<(), String> {
+    sqlx::query(statement).execute(pool).await.map_err(|error| error.to_string())?;
+    println!("{} completed", statement.trim_end_matches(';'));
+    Ok(())
+}
+
+pub async fn command(config: &Config, args: &[String]) -> Result<(), String> {
+    let usage = "Usage: dash_server db backup <path>|restore <path>|vacuum|analyze";
+    let database = &config.database;
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("backup"), Some(path)) if args.len() == 2 => {
+            backup(database, Path::new(path)).await?;
+            println!("Database backed up to {}", path);
+            Ok(())
+        }
+        (Some("restore"), Some(path)) if args.len() == 2 => {
+            restore(database, Path::new(path)).await?;
+            println!("Database restored from {}", path);
+            Ok(())
+        }
+        (Some(action @ ("vacuum" | "analyze")), None) => {
+            let pool = pool::create_pool(database)
+                .await
+                .map_err(|error| format!("Could not create database pool: {}", error))?;
+            let statement = if action == "vacuum" { "VACUUM;" } else { "ANALYZE;" };
+            maintain(&pool, statement).await
+        }
+        _ => Err(String::from(usage)),
+    }
+}
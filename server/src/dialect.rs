@@ -0,0 +1,77 @@
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dialect {
+    Postgres,
+    Sqlite,
+}
+
+impl Dialect {
+    pub fn from_url(database_url: &str) -> Option<Self> {
+        let (scheme, _) = database_url.split_once(':')?;
+        match scheme {
+            "postgres" | "postgresql" => Some(Dialect::Postgres),
+            "sqlite" => Some(Dialect::Sqlite),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dialect::Postgres => "postgres",
+            Dialect::Sqlite => "sqlite",
+        }
+    }
+
+    pub fn is_in_memory(&self, database_url: &str) -> bool {
+        match self {
+            Dialect::Postgres => false,
+            Dialect::Sqlite => {
+                database_url.contains(":memory:") || database_url.contains("mode=memory")
+            }
+        }
+    }
+
+    pub fn select_bool(&self, column: &str) -> String {
+        match self {
+            Dialect::Postgres => column.to_string(),
+            Dialect::Sqlite => format!("CAST({} AS INTEGER) AS {}", column, column),
+        }
+    }
+
+    pub fn select_uuid(&self, column: &str) -> String {
+        match self {
+            Dialect::Postgres => format!("CAST({} AS TEXT) AS {}", column, column),
+            Dialect::Sqlite => column.to_string(),
+        }
+    }
+
+    pub fn select_timestamp(&self, column: &str) -> String {
+        match self {
+            Dialect::Postgres => format!(
+                "to_char({} AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS.US\"Z\"') AS {}",
+                column, column
+            ),
+            Dialect::Sqlite => column.to_string(),
+        }
+    }
+
+    pub fn bind_uuid(&self, index: usize) -> String {
+        match self {
+            Dialect::Postgres => format!("CAST(${} AS UUID)", index),
+            Dialect::Sqlite => format!("${}", index),
+        }
+    }
+
+    pub fn current_timestamp(&self) -> &'static str {
+        match self {
+            Dialect::Postgres => "CURRENT_TIMESTAMP",
+            Dialect::Sqlite => "strftime('%Y-%m-%dT%H:%M:%fZ', 'now')",
+        }
+    }
+
+    pub fn supports_notify(&self) -> bool {
+        match self {
+            Dialect::Postgres => true,
+            Dialect::Sqlite => false,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fmt;
+
+use axum::extract::multipart::MultipartRejection;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection, StringRejection};
+use axum::response::{IntoResponse, Response};
+use dash_types::error::{ApiErrorCode, FieldError, PROBLEM_CONTENT_TYPE};
+use http::StatusCode;
+use http::header::CONTENT_TYPE;
+use tracing::error;
+
+use crate::metrics::{record_acquire_timeout, record_auth_outcome};
+use crate::middleware::request_context;
+
+#[derive(Debug)]
+pub enum DbError {
+    UniqueViolation(Option<String>),
+    NotFound,
+    PoolTimeout,
+    Other(sqlx::Error),
+}
+
+impl DbError {
+    pub fn is_serialization_failure(&self) -> bool {
+        let DbError::Other(sqlx::Error::Database(error)) = self else {
+            return false;
+        };
+        matches!(error.code().as_deref(), Some("40001" | "40P01" | "5" | "6" | "517"))
+    }
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(error: sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => DbError::NotFound,
+            sqlx::Error::PoolTimedOut => {
+                record_acquire_timeout();
+                DbError::PoolTimeout
+            }
+            sqlx::Error::Database(error) if error.is_unique_violation() => {
+                DbError::UniqueViolation(error.constraint().map(String::from))
+            }
+            error => DbError::Other(error),
+        }
+    }
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::UniqueViolation(Some(constraint)) => {
+                write!(f, "Unique constraint violated: {}", constraint)
+            }
+            DbError::UniqueViolation(None) => write!(f, "Unique constraint violated"),
+            DbError::NotFound => write!(f, "Row not found"),
+            DbError::PoolTimeout => write!(f, "Timed out waiting for a database connection"),
+            DbError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for DbError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbError::Other(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ApiError(dash_types::error::ApiError);
+
+impl ApiError {
+    pub fn from_code(code: ApiErrorCode) -> Self {
+        Self(dash_types::error::ApiError::from_code(code))
+    }
+
+    pub fn with_detail(code: ApiErrorCode, detail: String) -> Self {
+        Self(dash_types::error::ApiError::with_detail(code, detail))
+    }
+
+    pub fn validation(errors: Vec<FieldError>) -> Self {
+        Self(dash_types::error::ApiError::validation(errors))
+    }
+
+    fn rejection(code: ApiErrorCode, status: StatusCode, detail: String) -> Self {
+        let mut error = Self::with_detail(code, detail);
+        error.0.status = status;
+        error
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.0.status
+    }
+
+    pub fn code(&self) -> ApiErrorCode {
+        self.0.code
+    }
+
+    pub fn detail(&self) -> String {
+        self.0.detail()
+    }
+}
+
+impl From<DbError> for ApiError {
+    fn from(error: DbError) -> Self {
+        match error {
+            DbError::NotFound => Self::from_code(ApiErrorCode::NotFound),
+            DbError::UniqueViolation(_) => Self::from_code(ApiErrorCode::Conflict),
+            DbError::PoolTimeout => {
+                error!(%error, "Database error");
+                Self::from_code(ApiErrorCode::ServiceUnavailable)
+            }
+            DbError::Other(_) => {
+                error!(%error, "Database error");
+                Self::from_code(ApiErrorCode::ServerError)
+            }
+        }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::from(DbError::from(error))
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::rejection(ApiErrorCode::InvalidBody, rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<StringRejection> for ApiError {
+    fn from(rejection: StringRejection) -> Self {
+        Self::rejection(ApiErrorCode::InvalidBody, rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<MultipartRejection> for ApiError {
+    fn from(rejection: MultipartRejection) -> Self {
+        Self::rejection(ApiErrorCode::InvalidBody, rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self::rejection(ApiErrorCode::InvalidPath, rejection.status(), rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::rejection(ApiErrorCode::InvalidQuery, rejection.status(), rejection.body_text())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if matches!(
+            self.code(),
+            ApiErrorCode::Unauthorized
+                | ApiErrorCode::WrongCredentials
+                | ApiErrorCode::InvalidToken
+                | ApiErrorCode::AccessDenied
+                | ApiErrorCode::UserDisabled
+        ) {
+            record_auth_outcome(&self.code().to_string());
+        }
+        let context = request_context::current();
+        let problem = self.0.to_problem(
+            context.as_ref().and_then(|context| context.request_id.clone()),
+            context.map(|context| context.path),
+        );
+        (self.status(), [(CONTENT_TYPE, PROBLEM_CONTENT_TYPE)], axum::Json(problem)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::{get, post};
+    use axum::{Router, middleware};
+    use dash_types::error::PROBLEM_CONTENT_TYPE;
+    use http::header::CONTENT_TYPE;
+    use http::{Method, Request};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::extract::{Json, Multipart, Path};
+    use crate::middleware::request_context::request_context;
+    use crate::test_utils::send;
+
+    fn router() -> Router {
+        Router::new()
+            .route("/items/{id}", get(|Path(id): Path<i64>| async move { id.to_string() }))
+            .route("/items", post(|Json(value): Json<Value>| async move { Json(value) }))
+            .route("/uploads", post(|Multipart(_): Multipart| async {}))
+            .layer(middleware::from_fn(request_context))
+    }
+
+    #[tokio::test]
+    async fn rejections_are_problem_details() {
+        let router = router();
+
+        let (status, headers, body) = send(&router, Method::POST, "/items", None, "{").await;
+        assert_eq!(status, 400);
+        assert_eq!(headers[CONTENT_TYPE], PROBLEM_CONTENT_TYPE);
+        assert_eq!(body["code"], "invalid_body");
+        assert_eq!(body["type"], "urn:dash:problem:invalid_body");
+        assert_eq!(body["title"], "Invalid request body");
+        assert_eq!(body["status"], 400);
+        assert!(body["detail"].is_string());
+
+        let (status, _, body) = send(&router, Method::GET, "/items/abc", None, "").await;
+        assert_eq!(status, 400);
+        assert_eq!(body["code"], "invalid_path");
+        assert_eq!(body["instance"], "/items/abc");
+
+        let (status, headers, body) = send(&router, Method::POST, "/uploads", None, "").await;
+        assert_eq!(status, 400);
+        assert_eq!(headers[CONTENT_TYPE], PROBLEM_CONTENT_TYPE);
+        assert_eq!(body["code"], "invalid_body");
+    }
+
+    #[tokio::test]
+    async fn problem_details_include_request_id() {
+        let request = Request::builder()
+            .uri("/items/abc")
+            .header("x-request-id", "8c3f1b2a")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["request_id"], "8c3f1b2a");
+    }
+}
@@ -1,53 +1,194 @@
-use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::sync::Arc;
+use std::{env, process};
 
 use axum::Router;
+use dash_types::error::ApiErrorCode;
+use http::HeaderName;
 use http::header::{AUTHORIZATION, CONTENT_TYPE};
-use tokio::net::TcpListener;
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::{Level, debug, info};
 
+use crate::config::Config;
+use crate::error::ApiError;
+use crate::state::AppState;
+use crate::strategies::{realtime_strategy, session_strategy};
+
+mod config;
 mod controllers;
+mod db;
+mod dialect;
+mod error;
+mod extract;
+mod metrics;
 mod middleware;
+mod migrate;
+mod openapi;
 mod pool;
+mod pubsub;
+mod repositories;
+mod seed;
+mod server;
+mod state;
+mod storage;
 mod strategies;
+mod telemetry;
+#[cfg(test)]
+mod test_utils;
+mod tls;
+mod transaction;
+mod user;
+
+async fn not_found() -> ApiError {
+    ApiError::from_code(ApiErrorCode::NotFound)
+}
 
 #[tokio::main]
 async fn main() {
-    if cfg!(debug_assertions) {
-        let _ = match dotenvy::dotenv() {
-            Ok(path) => {
-                println!("Found .env file at {}", path.display());
-                path
+    let dotenv = dotenvy::dotenv();
+
+    let config_args = match config::parse_args(env::args().skip(1)) {
+        Ok(config_args) => config_args,
+        Err(error) => {
+            println!("{}", error);
+            process::exit(2);
+        }
+    };
+    if config_args.command.first().map(String::as_str) == Some("config") {
+        if let Err(error) = config::command(&config_args) {
+            println!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
+    let config = match Config::load(&config_args) {
+        Ok(config) => Arc::new(config),
+        Err(errors) => {
+            config::print_errors(&errors);
+            process::exit(1);
+        }
+    };
+    let telemetry = match telemetry::init(&config.log) {
+        Ok(telemetry) => telemetry,
+        Err(error) => {
+            println!("Could not initialize logging: {}", error);
+            process::exit(1);
+        }
+    };
+    match dotenv {
+        Ok(path) => info!("Found .env file at {}", path.display()),
+        Err(error) => debug!("Cannot access .env file: {}", error),
+    }
+
+    let args = config_args.command;
+    match args.first().map(String::as_str) {
+        Some("migrate") => {
+            if let Err(error) = migrate::command(&config, &args[1..]).await {
+                println!("{}", error);
+                process::exit(1);
             }
-            Err(error) => {
-                println!("Cannot access .env file: {}", error);
-                PathBuf::from("")
+            return;
+        }
+        Some("db") => {
+            if let Err(error) = db::command(&config, &args[1..]).await {
+                println!("{}", error);
+                process::exit(1);
+            }
+            return;
+        }
+        Some("seed") => {
+            if let Err(error) = seed::command(&config, &args[1..]).await {
+                println!("{}", error);
+                process::exit(1);
             }
-        };
+            return;
+        }
+        Some("user") => {
+            if let Err(error) = user::command(config.clone(), &args[1..]).await {
+                println!("{}", error);
+                process::exit(1);
+            }
+            return;
+        }
+        Some(command) => {
+            println!("Unknown command: {}", command);
+            process::exit(2);
+        }
+        None => {}
     }
 
-    pool::create_pool().await;
+    let database = &config.database;
+    let pool = match pool::create_pool(database).await {
+        Ok(pool) => pool,
+        Err(error) => {
+            panic!("Could not create database pool: {}", error);
+        }
+    };
+    migrate::prepare_database(&pool, database).await;
+    let replica = match pool::create_replica_pool(database).await {
+        Ok(replica) => replica,
+        Err(error) => {
+            panic!("Could not create read replica pool: {}", error);
+        }
+    };
+    let pubsub = match pubsub::create_pubsub(database, config.ws.channel_capacity).await {
+        Ok(pubsub) => pubsub,
+        Err(error) => {
+            panic!("Could not create Postgres pub/sub: {}", error);
+        }
+    };
+    let storage = storage::create_storage(&config.storage);
+    db::schedule_backups(database, &config.backup);
+
+    let state = AppState::new(config.clone(), pool, replica, pubsub, storage);
+    tokio::spawn(session_strategy::track_sessions(state.pubsub.clone(), state.sessions.clone()));
+    tokio::spawn(realtime_strategy::track_typing(state.pubsub.clone(), state.typing.clone()));
+    tokio::spawn(realtime_strategy::prune_rate_limits(state.rate_limits.clone(), config.clone()));
 
     let cors = CorsLayer::permissive()
         .allow_origin(Any)
-        .allow_headers([AUTHORIZATION, CONTENT_TYPE])
+        .allow_headers([AUTHORIZATION, CONTENT_TYPE, HeaderName::from_static("last-event-id")])
         .expose_headers(Any);
 
-    let app = Router::new()
-        .nest("/auth", controllers::auth_controller::routes())
-        .nest("/user", controllers::user_controller::routes())
-        .nest("/ws", controllers::ws_controller::routes())
-        .layer(ServiceBuilder::new().layer(cors));
-
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3001));
-    let listener = TcpListener::bind(addr).await.unwrap();
-    match axum::serve(listener, app).await {
-        Ok(_) => {
-            println!("Server listening on http://{}", addr)
-        }
-        Err(error) => {
-            panic!("Could not bind to http://{}: {}", addr, error)
+    let mut app = Router::new()
+        .nest("/attachments", controllers::attachment_controller::routes(&state))
+        .nest("/auth", controllers::auth_controller::routes(&state))
+        .nest("/chat", controllers::chat_controller::routes(&state))
+        .nest("/database", controllers::database_controller::routes(&state))
+        .nest("/events", controllers::events_controller::routes(&state))
+        .nest("/health", controllers::health_controller::routes())
+        .nest("/notifications", controllers::notification_controller::routes(&state))
+        .nest("/user", controllers::user_controller::routes(&state))
+        .nest("/ws", controllers::ws_controller::routes(&state))
+        .merge(controllers::docs_controller::routes())
+        .fallback(not_found);
+    let metrics = &config.metrics;
+    if metrics.enabled {
+        match metrics.port {
+            Some(port) => {
+                let routes = controllers::metrics_controller::routes().with_state(state.clone());
+                server::serve_metrics(&metrics.host, port, routes).await;
+            }
+            None => app = app.merge(controllers::metrics_controller::routes()),
         }
+        app = app.route_layer(axum::middleware::from_fn(middleware::metrics::track_metrics));
     }
+
+    let app = app.with_state(state.clone()).layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(telemetry::request_span)
+                    .on_response(DefaultOnResponse::new().level(Level::INFO)),
+            )
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(cors)
+            .layer(axum::middleware::from_fn(middleware::request_context::request_context)),
+    );
+
+    server::serve(&config.server, app, state).await;
+    telemetry.shutdown();
 }
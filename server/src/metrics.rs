@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use http::StatusCode;
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Registry,
+    TextEncoder, register_gauge_vec_with_registry, register_histogram_vec_with_registry,
+    register_int_counter_vec_with_registry, register_int_counter_with_registry,
+    register_int_gauge_vec_with_registry, register_int_gauge_with_registry,
+};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+use crate::pool::{DbPool, pool_stats};
+use crate::state::AppState;
+
+pub const QUERY_TARGET: &str = "sqlx::query";
+const QUERY_OPERATIONS: [&str; 5] = ["select", "insert", "update", "delete", "with"];
+const QUERY_BUCKETS: [f64; 12] =
+    [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    auth_outcomes: IntCounterVec,
+    ws_connections: IntGauge,
+    ws_lagged_clients: IntCounter,
+    ws_missed_messages: IntCounter,
+    ws_dropped_clients: IntCounter,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGaugeVec,
+    db_pool_saturation: GaugeVec,
+    db_acquire_timeouts: IntCounter,
+    db_query_duration: HistogramVec,
+}
+
+impl Metrics {
+    fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new_custom(Some(String::from("dash")), None)?;
+        let build_info = register_int_gauge_vec_with_registry!(
+            "build_info",
+            "Build information of the running server",
+            &["version", "profile"],
+            registry
+        )?;
+        let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+        build_info.with_label_values(&[env!("CARGO_PKG_VERSION"), profile]).set(1);
+
+        Ok(Self {
+            http_requests: register_int_counter_vec_with_registry!(
+                "http_requests_total",
+                "HTTP requests by route and status",
+                &["method", "route", "status"],
+                registry
+            )?,
+            http_duration: register_histogram_vec_with_registry!(
+                "http_request_duration_seconds",
+                "HTTP request latency by route and status",
+                &["method", "route", "status"],
+                registry
+            )?,
+            auth_outcomes: register_int_counter_vec_with_registry!(
+                "auth_outcomes_total",
+                "Successful authentications and authentication errors by code",
+                &["outcome"],
+                registry
+            )?,
+            ws_connections: register_int_gauge_with_registry!(
+                "ws_connections",
+                "Open WebSocket connections",
+                registry
+            )?,
+            ws_lagged_clients: register_int_counter_with_registry!(
+                "ws_lagged_clients_total",
+                "Times a WebSocket client fell behind the broadcast channel",
+                registry
+            )?,
+            ws_missed_messages: register_int_counter_with_registry!(
+                "ws_missed_messages_total",
+                "Broadcast messages skipped by lagging WebSocket clients",
+                registry
+            )?,
+            ws_dropped_clients: register_int_counter_with_registry!(
+                "ws_dropped_clients_total",
+                "WebSocket clients disconnected for a full outbound queue",
+                registry
+            )?,
+            db_pool_connections: register_int_gauge_vec_with_registry!(
+                "db_pool_connections",
+                "Database pool connections by state",
+                &["pool", "state"],
+                registry
+            )?,
+            db_pool_max_connections: register_int_gauge_vec_with_registry!(
+                "db_pool_max_connections",
+                "Database pool connection limit",
+                &["pool"],
+                registry
+            )?,
+            db_pool_saturation: register_gauge_vec_with_registry!(
+                "db_pool_saturation",
+                "Share of the database pool limit in use",
+                &["pool"],
+                registry
+            )?,
+            db_acquire_timeouts: register_int_counter_with_registry!(
+                "db_acquire_timeouts_total",
+                "Requests that timed out waiting for a database connection",
+                registry
+            )?,
+            db_query_duration: register_histogram_vec_with_registry!(
+                "db_query_duration_seconds",
+                "Database query latency by statement type",
+                &["operation"],
+                QUERY_BUCKETS.to_vec(),
+                registry
+            )?,
+            registry,
+        })
+    }
+}
+
+static METRICS: Lazy<Metrics> =
+    Lazy::new(|| Metrics::new().expect("Could not register Prometheus metrics"));
+
+pub fn record_request(method: &str, route: &str, status: StatusCode, duration: Duration) {
+    let status = status.as_str();
+    METRICS.http_requests.with_label_values(&[method, route, status]).inc();
+    METRICS
+        .http_duration
+        .with_label_values(&[method, route, status])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_auth_outcome(outcome: &str) {
+    METRICS.auth_outcomes.with_label_values(&[outcome]).inc();
+}
+
+pub fn record_ws_lag(missed: u64) {
+    METRICS.ws_lagged_clients.inc();
+    METRICS.ws_missed_messages.inc_by(missed);
+}
+
+pub fn record_ws_dropped() {
+    METRICS.ws_dropped_clients.inc();
+}
+
+pub fn record_acquire_timeout() {
+    METRICS.db_acquire_timeouts.inc();
+}
+
+pub fn acquire_timeouts() -> u64 {
+    METRICS.db_acquire_timeouts.get()
+}
+
+fn set_pool_metrics(name: &str, pool: &DbPool) {
+    let stats = pool_stats(pool);
+    METRICS.db_pool_connections.with_label_values(&[name, "idle"]).set(stats.idle as i64);
+    METRICS.db_pool_connections.with_label_values(&[name, "in_use"]).set(stats.in_use as i64);
+    METRICS.db_pool_max_connections.with_label_values(&[name]).set(stats.max_connections as i64);
+    METRICS.db_pool_saturation.with_label_values(&[name]).set(stats.saturation);
+}
+
+pub fn render(state: &AppState) -> Result<String, String> {
+    METRICS.ws_connections.set(state.ws.stats().connections as i64);
+    set_pool_metrics("primary", &state.pool);
+    if let Some(replica) = &state.replica {
+        set_pool_metrics("replica", replica);
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&METRICS.registry.gather(), &mut buffer)
+        .map_err(|error| error.to_string())?;
+    String::from_utf8(buffer).map_err(|error| error.to_string())
+}
+
+#[derive(Default)]
+struct QueryVisitor {
+    operation: Option<String>,
+    elapsed: Option<f64>,
+}
+
+impl Visit for QueryVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.elapsed = Some(value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "summary" {
+            let operation = value.split_whitespace().next().unwrap_or_default().to_lowercase();
+            self.operation = Some(operation);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+pub struct QueryMetrics;
+
+impl<S: Subscriber> Layer<S> for QueryMetrics {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        if event.metadata().target() != QUERY_TARGET {
+            return;
+        }
+        let mut visitor = QueryVisitor::default();
+        event.record(&mut visitor);
+        if let Some(elapsed) = visitor.elapsed {
+            let operation = visitor
+                .operation
+                .as_deref()
+                .filter(|operation| QUERY_OPERATIONS.contains(operation))
+                .unwrap_or("other");
+            METRICS.db_query_duration.with_label_values(&[operation]).observe(elapsed);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tokio::time::Instant;
+
+use crate::metrics::record_request;
+
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+pub async fn track_metrics(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| String::from(UNMATCHED_ROUTE));
+    let method = request.method().to_string();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+    record_request(&method, &route, response.status(), start.elapsed());
+    response
+}
@@ -1 +1,3 @@
 pub mod auth_token;
+pub mod metrics;
+pub mod request_context;
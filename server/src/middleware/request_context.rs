@@ -0,0 +1,31 @@
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    pub request_id: Option<String>,
+    pub path: String,
+}
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+pub fn current() -> Option<RequestContext> {
+    REQUEST_CONTEXT.try_with(RequestContext::clone).ok()
+}
+
+pub async fn request_context(request: Request, next: Next) -> Response {
+    let context = RequestContext {
+        request_id: request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from),
+        path: request.uri().path().to_string(),
+    };
+    REQUEST_CONTEXT.scope(context, next.run(request)).await
+}
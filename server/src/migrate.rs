@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use tracing::info;
+
+use crate::config::Config;
+use crate::dialect::Dialect;
+use crate::pool::{self, DatabaseConfig, DbPool};
+
+static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("../migrations/postgres");
+
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("../migrations/sqlite");
+
+#[derive(Debug, PartialEq)]
+enum MigrationState {
+    Applied,
+    Pending,
+    Modified,
+}
+
+pub fn get_migrator(dialect: Dialect) -> &'static Migrator {
+    match dialect {
+        Dialect::Postgres => &POSTGRES_MIGRATOR,
+        Dialect::Sqlite => &SQLITE_MIGRATOR,
+    }
+}
+
+async fn get_migration_states(
+    pool: &DbPool,
+    migrator: &'static Migrator,
+    create_table: bool,
+) -> Result<Vec<(i64, &'static str, MigrationState)>, MigrateError> {
+    let mut connection = pool.acquire().await?;
+    if create_table {
+        connection.ensure_migrations_table().await?;
+    }
+    if let Some(version) = connection.dirty_version().await? {
+        return Err(MigrateError::Dirty(version));
+    }
+
+    let applied: HashMap<_, _> = connection
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum))
+        .collect();
+    Ok(migrator
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let state = match applied.get(&migration.version) {
+                Some(checksum) if *checksum == migration.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+                None => MigrationState::Pending,
+            };
+            (migration.version, migration.description.as_ref(), state)
+        })
+        .collect())
+}
+
+async fn outdated_migrations(
+    pool: &DbPool,
+    migrator: &'static Migrator,
+    create_table: bool,
+) -> Result<(), String> {
+    let states = get_migration_states(pool, migrator, create_table)
+        .await
+        .map_err(|error| error.to_string())?;
+    let outdated: Vec<String> = states
+        .into_iter()
+        .filter(|(_, _, state)| *state != MigrationState::Applied)
+        .map(|(version, description, state)| format!("{} {} ({:?})", version, description, state))
+        .collect();
+    if outdated.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Database migrations are not applied: {}", outdated.join(", ")))
+    }
+}
+
+async fn verify_migrations(pool: &DbPool, migrator: &'static Migrator) -> Result<(), String> {
+    outdated_migrations(pool, migrator, true).await
+}
+
+pub async fn check_migrations(pool: &DbPool, migrator: &'static Migrator) -> Result<(), String> {
+    outdated_migrations(pool, migrator, false).await
+}
+
+pub async fn prepare_database(pool: &DbPool, database: &DatabaseConfig) {
+    let migrator = get_migrator(database.dialect);
+    match database.migrations.as_str() {
+        "run" => match migrator.run(pool).await {
+            Ok(()) => info!("Database migrations applied"),
+            Err(error) => panic!("Could not apply database migrations: {}", error),
+        },
+        "verify" => match verify_migrations(pool, migrator).await {
+            Ok(()) => info!("Database migrations verified"),
+            Err(error) => panic!("{}", error),
+        },
+        "off" => {}
+        mode => panic!("Unknown DATABASE_MIGRATIONS mode: {}", mode),
+    }
+}
+
+async fn migrate_down(
+    pool: &DbPool,
+    migrator: &'static Migrator,
+    target: Option<i64>,
+) -> Result<(), MigrateError> {
+    let target = match target {
+        Some(target) => target,
+        None => {
+            let mut applied: Vec<i64> = get_migration_states(pool, migrator, true)
+                .await?
+                .into_iter()
+                .filter(|(_, _, state)| *state != MigrationState::Pending)
+                .map(|(version, _, _)| version)
+                .collect();
+            applied.pop();
+            applied.pop().unwrap_or(0)
+        }
+    };
+    migrator.undo(pool, target).await
+}
+
+async fn print_status(pool: &DbPool, migrator: &'static Migrator) -> Result<(), MigrateError> {
+    for (version, description, state) in get_migration_states(pool, migrator, true).await? {
+        let state = match state {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+        };
+        println!("{:<16} {:<10} {}", version, state, description);
+    }
+    Ok(())
+}
+
+pub async fn command(config: &Config, args: &[String]) -> Result<(), String> {
+    let action = match args.first().map(String::as_str) {
+        Some(action @ ("up" | "down" | "status")) => action,
+        _ => return Err(String::from("Usage: dash_server migrate up|down [version]|status")),
+    };
+    let target = match args.get(1) {
+        Some(target) if action == "down" => {
+            Some(target.parse().map_err(|_| format!("Invalid migration version: {}", target))?)
+        }
+        Some(arg) => return Err(format!("Unexpected argument: {}", arg)),
+        None => None,
+    };
+
+    let database = &config.database;
+    let pool = pool::create_pool(database)
+        .await
+        .map_err(|error| format!("Could not create database pool: {}", error))?;
+    let migrator = get_migrator(database.dialect);
+    let result = match action {
+        "up" => migrator.run(&pool).await,
+        "down" => migrate_down(&pool, migrator, target).await,
+        _ => print_status(&pool, migrator).await,
+    };
+    result.map_err(|error| error.to_string())?;
+    if action != "status" {
+        print_status(&pool, migrator).await.map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use dash_types::auth::AuthToken;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::controllers::auth_controller::AuthApi;
+use crate::controllers::user_controller::UserApi;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "request_token",
+            bearer("Request token returned by /auth/register and /auth/login"),
+        );
+        components
+            .add_security_scheme("access_token", bearer("Access token returned by /auth/request"));
+    }
+}
+
+fn bearer(description: &str) -> SecurityScheme {
+    SecurityScheme::Http(
+        HttpBuilder::new()
+            .scheme(HttpAuthScheme::Bearer)
+            .bearer_format("JWT")
+            .description(Some(description))
+            .build(),
+    )
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    nest((path = "/auth", api = AuthApi), (path = "/user", api = UserApi)),
+    components(schemas(AuthToken)),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Registration, sign-in and token exchange"),
+        (name = "user", description = "User accounts and administration"),
+    )
+)]
+struct ApiDoc;
+
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
@@ -1,125 +1,175 @@
-use std::env;
 use std::time::Duration;
 
-use once_cell::sync::OnceCell;
-use sqlx::Pool;
-use sqlx::any::Any;
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-use sqlx::any::AnyPoolOptions;
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-use sqlx::{PgPool, postgres::PgPoolOptions};
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
-
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-static POOL: OnceCell<Pool<Any>> = OnceCell::new();
-
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-static POOL: OnceCell<PgPool> = OnceCell::new();
-
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-static POOL: OnceCell<SqlitePool> = OnceCell::new();
-
-pub async fn create_pool() {
-    let database_url = match env::var("DATABASE_URL") {
-        Ok(url) => url,
-        Err(error) => {
-            panic!("Error getting DATABASE_URL: {}", error);
+use dash_types::database::PoolStats;
+use sqlx::any::{Any, AnyPoolOptions};
+use sqlx::migrate::MigrateDatabase;
+use sqlx::{AnyConnection, Connection, Executor, Pool};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::dialect::Dialect;
+
+pub type DbPool = Pool<Any>;
+
+const CONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub dialect: Dialect,
+    pub migrations: String,
+    pub connect_attempts: u32,
+    pub connect_backoff: Duration,
+    pub sqlite_journal_mode: String,
+    pub sqlite_busy_timeout: Duration,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub max_lifetime: Duration,
+    pub statement_timeout: Duration,
+    pub replica_url: Option<String>,
+}
+
+impl DatabaseConfig {
+    pub fn is_in_memory(&self) -> bool {
+        self.dialect.is_in_memory(&self.url)
+    }
+
+    pub fn connect_statements(&self, read_only: bool) -> Option<String> {
+        let mut statements = Vec::new();
+        match self.dialect {
+            Dialect::Postgres => {
+                if !self.statement_timeout.is_zero() {
+                    statements.push(format!(
+                        "SET statement_timeout = {};",
+                        self.statement_timeout.as_millis()
+                    ));
+                }
+                if read_only {
+                    statements.push(String::from("SET default_transaction_read_only = on;"));
+                }
+            }
+            Dialect::Sqlite => {
+                if !self.is_in_memory() && !read_only {
+                    statements.push(format!("PRAGMA journal_mode = {};", self.sqlite_journal_mode));
+                }
+                statements.push(format!(
+                    "PRAGMA busy_timeout = {};",
+                    self.sqlite_busy_timeout.as_millis()
+                ));
+                if read_only {
+                    statements.push(String::from("PRAGMA query_only = ON;"));
+                }
+            }
         }
-    };
 
-    init_pool(database_url).await;
+        if statements.is_empty() { None } else { Some(statements.join(" ")) }
+    }
 }
 
-#[cfg(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres"))
-))]
-async fn init_pool(_database_url: String) {
-    panic!("Cannot enable both postgres and sqlite features at the same time");
+pub fn pool_stats(pool: &DbPool) -> PoolStats {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+    let max_connections = pool.options().get_max_connections();
+    let in_use = size.saturating_sub(idle);
+    PoolStats {
+        size,
+        idle,
+        in_use,
+        max_connections,
+        min_connections: pool.options().get_min_connections(),
+        saturation: in_use as f64 / max_connections as f64,
+    }
 }
 
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-async fn init_pool(database_url: String) {
-    sqlx::any::install_default_drivers();
-
-    let pool = match AnyPoolOptions::new()
-        .max_connections(100)
-        .idle_timeout(Some(Duration::from_millis(1000)))
-        .connect(&database_url)
-        .await
-    {
-        Ok(pool) => {
-            println!("Database pool created from database_url: {}", database_url);
-            pool
+fn is_transient(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(error) => {
+            error.code().is_some_and(|code| code.starts_with("08") || code == "57P03")
         }
-        Err(error) => {
-            panic!("Could not create database pool: {}", error);
-        }
-    };
-
-    POOL.set(pool).unwrap();
+        _ => false,
+    }
 }
 
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-async fn init_pool(database_url: String) {
-    let pool = match PgPoolOptions::new()
-        .max_connections(100)
-        .idle_timeout(Some(Duration::from_millis(1000)))
-        .connect(&database_url)
-        .await
-    {
-        Ok(pool) => {
-            println!("Database pool created from database_url: {}", database_url);
-            pool
-        }
-        Err(error) => {
-            panic!("Could not create database pool: {}", error);
-        }
-    };
+async fn ensure_database(config: &DatabaseConfig) -> Result<(), sqlx::Error> {
+    if config.dialect != Dialect::Sqlite || config.is_in_memory() {
+        return Ok(());
+    }
 
-    POOL.set(pool).unwrap();
+    if !Any::database_exists(&config.url).await? {
+        Any::create_database(&config.url).await?;
+        info!("Created SQLite database");
+    }
+    Ok(())
 }
 
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-async fn init_pool(database_url: String) {
-    let pool = match SqlitePoolOptions::new()
-        .max_connections(100)
-        .idle_timeout(Some(Duration::from_millis(1000)))
-        .connect(&database_url)
-        .await
-    {
-        Ok(pool) => {
-            println!("Database pool created from database_url: {}", database_url);
-            pool
-        }
-        Err(error) => {
-            panic!("Could not create database pool: {}", error);
+async fn wait_for_database(config: &DatabaseConfig, url: &str) -> Result<(), sqlx::Error> {
+    let mut backoff = config.connect_backoff;
+    let mut attempt = 1;
+    loop {
+        match AnyConnection::connect(url).await {
+            Ok(connection) => return connection.close().await,
+            Err(error) if attempt < config.connect_attempts && is_transient(&error) => {
+                warn!(
+                    "Could not connect to database (attempt {} of {}), retrying in {} ms: {}",
+                    attempt,
+                    config.connect_attempts,
+                    backoff.as_millis(),
+                    error
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(CONNECT_BACKOFF_MAX);
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
         }
-    };
-
-    POOL.set(pool).unwrap();
+    }
 }
 
-#[cfg(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres"))
-))]
-pub fn get_pool() -> Pool<Any> {
-    panic!("Cannot enable both postgres and sqlite features at the same time");
-}
-
-#[cfg(not(all(feature = "postgres", feature = "sqlite")))]
-pub fn get_pool() -> Pool<Any> {
-    POOL.get().unwrap().to_owned()
+async fn connect_pool(
+    config: &DatabaseConfig,
+    url: &str,
+    read_only: bool,
+) -> Result<DbPool, sqlx::Error> {
+    wait_for_database(config, url).await?;
+
+    let mut options = if config.dialect.is_in_memory(url) {
+        AnyPoolOptions::new().max_connections(1).idle_timeout(None).max_lifetime(None)
+    } else {
+        AnyPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .idle_timeout(Some(config.idle_timeout))
+            .max_lifetime(Some(config.max_lifetime))
+    };
+    options = options.acquire_timeout(config.acquire_timeout);
+    if let Some(statements) = config.connect_statements(read_only) {
+        options = options.after_connect(move |connection, _| {
+            let statements = statements.clone();
+            Box::pin(async move {
+                connection.execute(statements.as_str()).await?;
+                Ok(())
+            })
+        });
+    }
+    options.connect(url).await
 }
 
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-pub fn get_pool() -> PgPool {
-    POOL.get().unwrap().to_owned()
+pub async fn create_pool(config: &DatabaseConfig) -> Result<DbPool, sqlx::Error> {
+    sqlx::any::install_default_drivers();
+    ensure_database(config).await?;
+    let pool = connect_pool(config, &config.url, false).await?;
+    info!("Database pool created for {} backend", config.dialect.name());
+    Ok(pool)
 }
 
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-pub fn get_pool() -> SqlitePool {
-    POOL.get().unwrap().to_owned()
+pub async fn create_replica_pool(config: &DatabaseConfig) -> Result<Option<DbPool>, sqlx::Error> {
+    let Some(url) = &config.replica_url else {
+        return Ok(None);
+    };
+    let pool = connect_pool(config, url, true).await?;
+    info!("Read replica pool created for {} backend", config.dialect.name());
+    Ok(Some(pool))
 }
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use dash_types::ws::WsServerMessage;
+use futures::future::BoxFuture;
+use tokio::sync::broadcast;
+use tracing::info;
+
+use crate::pool::DatabaseConfig;
+
+pub trait PubSub: Send + Sync {
+    fn publish(&self, message: WsServerMessage);
+
+    fn subscribe(&self) -> broadcast::Receiver<WsServerMessage>;
+
+    fn flush(&self) -> BoxFuture<'_, ()>;
+}
+
+pub struct InProcessPubSub {
+    tx: broadcast::Sender<WsServerMessage>,
+}
+
+impl InProcessPubSub {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+}
+
+impl PubSub for InProcessPubSub {
+    fn publish(&self, message: WsServerMessage) {
+        let _ = self.tx.send(message);
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<WsServerMessage> {
+        self.tx.subscribe()
+    }
+
+    fn flush(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+}
+
+pub mod postgres {
+    use std::time::Duration;
+
+    use dash_types::ws::WsServerMessage;
+    use futures::future::BoxFuture;
+    use jsonwebtoken::get_current_timestamp;
+    use serde::{Deserialize, Serialize};
+    use sqlx::PgPool;
+    use sqlx::postgres::{PgListener, PgPoolOptions};
+    use tokio::sync::mpsc::error::TrySendError;
+    use tokio::sync::{broadcast, mpsc, oneshot};
+    use tracing::{error, info, warn};
+    use uuid::Uuid;
+
+    use super::PubSub;
+
+    const CHANNEL: &str = "dash_ws";
+    const MAX_PAYLOAD_SIZE: usize = 7900;
+    const STORED_PAYLOAD_EXPIRY: u64 = 300;
+    const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(tag = "kind", rename_all = "snake_case")]
+    enum Envelope {
+        Inline { message: WsServerMessage },
+        Stored { id: String },
+    }
+
+    enum Outbound {
+        Message(WsServerMessage),
+        Flush(oneshot::Sender<()>),
+    }
+
+    pub struct PgPubSub {
+        tx: broadcast::Sender<WsServerMessage>,
+        outbound: mpsc::Sender<Outbound>,
+    }
+
+    impl PgPubSub {
+        pub async fn connect(database_url: &str, capacity: usize) -> Result<Self, sqlx::Error> {
+            let pool = PgPoolOptions::new().max_connections(2).connect(database_url).await?;
+            let mut listener = PgListener::connect_with(&pool).await?;
+            listener.listen(CHANNEL).await?;
+
+            let (tx, _) = broadcast::channel(capacity);
+            let (outbound, outbound_rx) = mpsc::channel(capacity);
+            tokio::spawn(publish_loop(pool.clone(), outbound_rx));
+            tokio::spawn(listen_loop(pool, listener, tx.clone()));
+
+            Ok(Self { tx, outbound })
+        }
+    }
+
+    impl PubSub for PgPubSub {
+        fn publish(&self, message: WsServerMessage) {
+            if let Err(TrySendError::Full(_)) = self.outbound.try_send(Outbound::Message(message)) {
+                warn!("Postgres pub/sub queue is full, dropping WebSocket message");
+                let _ = self.tx.send(WsServerMessage::Lagged { missed: 1 });
+            }
+        }
+
+        fn subscribe(&self) -> broadcast::Receiver<WsServerMessage> {
+            self.tx.subscribe()
+        }
+
+        fn flush(&self) -> BoxFuture<'_, ()> {
+            Box::pin(async move {
+                let (done, flushed) = oneshot::channel();
+                if self.outbound.send(Outbound::Flush(done)).await.is_ok() {
+                    let _ = flushed.await;
+                }
+            })
+        }
+    }
+
+    async fn publish_loop(pool: PgPool, mut outbound: mpsc::Receiver<Outbound>) {
+        while let Some(outbound) = outbound.recv().await {
+            match outbound {
+                Outbound::Message(message) => {
+                    if let Err(error) = notify(&pool, message).await {
+                        error!(%error, "Error publishing WebSocket message");
+                    }
+                }
+                Outbound::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    async fn notify(pool: &PgPool, message: WsServerMessage) -> Result<(), sqlx::Error> {
+        let mut payload = serde_json::to_string(&Envelope::Inline { message }).unwrap();
+        if payload.len() > MAX_PAYLOAD_SIZE {
+            let id = Uuid::new_v4().to_string();
+            let now = get_current_timestamp() as i64;
+            sqlx::query("DELETE FROM \"pubsub_payloads\" WHERE created_at < $1;")
+                .bind(now - STORED_PAYLOAD_EXPIRY as i64)
+                .execute(pool)
+                .await?;
+            sqlx::query(
+                "INSERT INTO \"pubsub_payloads\" (id, payload, created_at) VALUES ($1, $2, $3);",
+            )
+            .bind(&id)
+            .bind(&payload)
+            .bind(now)
+            .execute(pool)
+            .await?;
+            payload = serde_json::to_string(&Envelope::Stored { id }).unwrap();
+        }
+
+        sqlx::query("SELECT pg_notify($1, $2);")
+            .bind(CHANNEL)
+            .bind(payload)
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    async fn listen_loop(
+        pool: PgPool,
+        mut listener: PgListener,
+        tx: broadcast::Sender<WsServerMessage>,
+    ) {
+        loop {
+            let notification = match listener.try_recv().await {
+                Ok(Some(notification)) => notification,
+                Ok(None) => {
+                    warn!("Postgres pub/sub connection lost, reconnecting");
+                    listener = relisten(&pool).await;
+                    let _ = tx.send(WsServerMessage::Lagged { missed: 0 });
+                    continue;
+                }
+                Err(error) => {
+                    error!(%error, "Error receiving WebSocket notification, reconnecting");
+                    listener = relisten(&pool).await;
+                    let _ = tx.send(WsServerMessage::Lagged { missed: 0 });
+                    continue;
+                }
+            };
+
+            match resolve(&pool, notification.payload()).await {
+                Ok(message) => {
+                    let _ = tx.send(message);
+                }
+                Err(error) => error!(%error, "Error resolving WebSocket notification"),
+            }
+        }
+    }
+
+    async fn relisten(pool: &PgPool) -> PgListener {
+        loop {
+            tokio::time::sleep(RECONNECT_BACKOFF).await;
+            let mut listener = match PgListener::connect_with(pool).await {
+                Ok(listener) => listener,
+                Err(error) => {
+                    error!(%error, "Error reconnecting Postgres pub/sub");
+                    continue;
+                }
+            };
+            match listener.listen(CHANNEL).await {
+                Ok(()) => {
+                    info!("Postgres pub/sub reconnected");
+                    return listener;
+                }
+                Err(error) => error!(%error, "Error listening for WebSocket notifications"),
+            }
+        }
+    }
+
+    async fn resolve(pool: &PgPool, payload: &str) -> Result<WsServerMessage, sqlx::Error> {
+        let envelope =
+            serde_json::from_str(payload).map_err(|error| sqlx::Error::Decode(Box::new(error)))?;
+        let payload: String = match envelope {
+            Envelope::Inline { message } => return Ok(message),
+            Envelope::Stored { id } => {
+                sqlx::query_scalar("SELECT payload FROM \"pubsub_payloads\" WHERE id = $1;")
+                    .bind(id)
+                    .fetch_one(pool)
+                    .await?
+            }
+        };
+
+        match serde_json::from_str(&payload) {
+            Ok(Envelope::Inline { message }) => Ok(message),
+            Ok(Envelope::Stored { id }) => Err(sqlx::Error::Protocol(format!(
+                "Stored payload {} references another stored payload",
+                id
+            ))),
+            Err(error) => Err(sqlx::Error::Decode(Box::new(error))),
+        }
+    }
+}
+
+pub async fn create_pubsub(
+    database: &DatabaseConfig,
+    capacity: usize,
+) -> Result<Arc<dyn PubSub>, sqlx::Error> {
+    if !database.dialect.supports_notify() {
+        return Ok(Arc::new(InProcessPubSub::new(capacity)));
+    }
+
+    let pubsub = postgres::PgPubSub::connect(&database.url, capacity).await?;
+    info!("Postgres pub/sub listening for WebSocket messages");
+    Ok(Arc::new(pubsub))
+}
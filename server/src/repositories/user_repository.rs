@@ -0,0 +1,236 @@
+use dash_types::user::{User, UserInfo};
+use futures::future::BoxFuture;
+use uuid::Uuid;
+
+use crate::dialect::Dialect;
+use crate::error::DbError;
+use crate::pool::DbPool;
+use crate::strategies::user_strategy::{
+    delete_user, get_all_users, get_user_by_username, get_user_by_username_or_email,
+    get_user_by_uuid, insert_user, set_user_flag,
+};
+
+#[derive(Clone, Debug)]
+pub struct NewUser {
+    pub uuid: Uuid,
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub is_admin: bool,
+}
+
+pub trait UserRepository: Send + Sync {
+    fn get_all(&self) -> BoxFuture<'_, Result<Vec<UserInfo>, DbError>>;
+
+    fn get_by_uuid<'a>(&'a self, uuid: &'a str) -> BoxFuture<'a, Result<User, DbError>>;
+
+    fn get_by_username<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<User, DbError>>;
+
+    fn get_by_username_or_email<'a>(
+        &'a self,
+        username_or_email: &'a str,
+    ) -> BoxFuture<'a, Result<User, DbError>>;
+
+    fn insert(&self, user: NewUser) -> BoxFuture<'_, Result<User, DbError>>;
+
+    fn delete<'a>(&'a self, uuid: &'a str) -> BoxFuture<'a, Result<u64, DbError>>;
+
+    fn set_disabled<'a>(
+        &'a self,
+        uuid: &'a str,
+        is_disabled: bool,
+    ) -> BoxFuture<'a, Result<u64, DbError>>;
+
+    fn set_moderator<'a>(
+        &'a self,
+        uuid: &'a str,
+        is_moderator: bool,
+    ) -> BoxFuture<'a, Result<u64, DbError>>;
+}
+
+pub struct SqlUserRepository {
+    pool: DbPool,
+    read_pool: DbPool,
+    dialect: Dialect,
+}
+
+impl SqlUserRepository {
+    pub fn new(pool: DbPool, read_pool: DbPool, dialect: Dialect) -> Self {
+        Self { pool, read_pool, dialect }
+    }
+}
+
+impl UserRepository for SqlUserRepository {
+    fn get_all(&self) -> BoxFuture<'_, Result<Vec<UserInfo>, DbError>> {
+        Box::pin(get_all_users(&self.read_pool, self.dialect))
+    }
+
+    fn get_by_uuid<'a>(&'a self, uuid: &'a str) -> BoxFuture<'a, Result<User, DbError>> {
+        Box::pin(get_user_by_uuid(&self.pool, self.dialect, uuid))
+    }
+
+    fn get_by_username<'a>(&'a self, username: &'a str) -> BoxFuture<'a, Result<User, DbError>> {
+        Box::pin(get_user_by_username(&self.pool, self.dialect, username))
+    }
+
+    fn get_by_username_or_email<'a>(
+        &'a self,
+        username_or_email: &'a str,
+    ) -> BoxFuture<'a, Result<User, DbError>> {
+        Box::pin(get_user_by_username_or_email(&self.pool, self.dialect, username_or_email))
+    }
+
+    fn insert(&self, user: NewUser) -> BoxFuture<'_, Result<User, DbError>> {
+        Box::pin(async move { insert_user(&self.pool, self.dialect, &user).await })
+    }
+
+    fn delete<'a>(&'a self, uuid: &'a str) -> BoxFuture<'a, Result<u64, DbError>> {
+        Box::pin(delete_user(&self.pool, self.dialect, uuid))
+    }
+
+    fn set_disabled<'a>(
+        &'a self,
+        uuid: &'a str,
+        is_disabled: bool,
+    ) -> BoxFuture<'a, Result<u64, DbError>> {
+        Box::pin(set_user_flag(&self.pool, self.dialect, "is_disabled", uuid, is_disabled))
+    }
+
+    fn set_moderator<'a>(
+        &'a self,
+        uuid: &'a str,
+        is_moderator: bool,
+    ) -> BoxFuture<'a, Result<u64, DbError>> {
+        Box::pin(set_user_flag(&self.pool, self.dialect, "is_moderator", uuid, is_moderator))
+    }
+}
+
+#[cfg(test)]
+pub mod memory {
+    use std::sync::Mutex;
+
+    use chrono::Utc;
+    use dash_types::user::{User, UserInfo};
+    use email_address::EmailAddress;
+    use futures::future::BoxFuture;
+
+    use super::{NewUser, UserRepository};
+    use crate::error::DbError;
+
+    #[derive(Default)]
+    pub struct InMemoryUserRepository {
+        users: Mutex<Vec<User>>,
+    }
+
+    impl InMemoryUserRepository {
+        fn find(&self, predicate: impl Fn(&User) -> bool) -> Result<User, DbError> {
+            let users = self.users.lock().unwrap();
+            users.iter().find(|user| predicate(user)).cloned().ok_or(DbError::NotFound)
+        }
+
+        fn update(&self, uuid: &str, apply: impl Fn(&mut User)) -> u64 {
+            let mut users = self.users.lock().unwrap();
+            users
+                .iter_mut()
+                .filter(|user| user.uuid.to_string() == uuid)
+                .map(|user| {
+                    apply(user);
+                    user.updated_at = Utc::now();
+                })
+                .count() as u64
+        }
+
+        pub fn set_admin(&self, uuid: &str, is_admin: bool) {
+            self.update(uuid, |user| user.is_admin = is_admin);
+        }
+    }
+
+    impl UserRepository for InMemoryUserRepository {
+        fn get_all(&self) -> BoxFuture<'_, Result<Vec<UserInfo>, DbError>> {
+            let users = self.users.lock().unwrap();
+            let users = users.iter().cloned().map(UserInfo::from_user).collect();
+            Box::pin(async move { Ok(users) })
+        }
+
+        fn get_by_uuid<'a>(&'a self, uuid: &'a str) -> BoxFuture<'a, Result<User, DbError>> {
+            let result = self.find(|user| user.uuid.to_string() == uuid);
+            Box::pin(async move { result })
+        }
+
+        fn get_by_username<'a>(
+            &'a self,
+            username: &'a str,
+        ) -> BoxFuture<'a, Result<User, DbError>> {
+            let username = username.to_lowercase();
+            let result = self.find(|user| user.username.to_lowercase() == username);
+            Box::pin(async move { result })
+        }
+
+        fn get_by_username_or_email<'a>(
+            &'a self,
+            username_or_email: &'a str,
+        ) -> BoxFuture<'a, Result<User, DbError>> {
+            let username_or_email = username_or_email.to_lowercase();
+            let result = self.find(|user| {
+                user.username.to_lowercase() == username_or_email
+                    || user.email.as_str().to_lowercase() == username_or_email
+            });
+            Box::pin(async move { result })
+        }
+
+        fn insert(&self, user: NewUser) -> BoxFuture<'_, Result<User, DbError>> {
+            let mut users = self.users.lock().unwrap();
+            let (username, email) = (user.username.to_lowercase(), user.email.to_lowercase());
+            let result = if users.iter().any(|existing| existing.uuid == user.uuid) {
+                Err(DbError::UniqueViolation(Some(String::from("users_uuid_key"))))
+            } else if users.iter().any(|existing| existing.username.to_lowercase() == username) {
+                Err(DbError::UniqueViolation(Some(String::from("users_username_lower_key"))))
+            } else if users.iter().any(|existing| existing.email.as_str().to_lowercase() == email) {
+                Err(DbError::UniqueViolation(Some(String::from("users_email_lower_key"))))
+            } else {
+                let now = Utc::now();
+                let user = User {
+                    id: users.iter().map(|user| user.id).max().unwrap_or(0) + 1,
+                    uuid: user.uuid,
+                    username: user.username,
+                    email: EmailAddress::new_unchecked(user.email),
+                    password: user.password,
+                    is_admin: user.is_admin,
+                    is_moderator: false,
+                    is_disabled: false,
+                    created_at: now,
+                    updated_at: now,
+                };
+                users.push(user.clone());
+                Ok(user)
+            };
+            Box::pin(async move { result })
+        }
+
+        fn delete<'a>(&'a self, uuid: &'a str) -> BoxFuture<'a, Result<u64, DbError>> {
+            let mut users = self.users.lock().unwrap();
+            let count = users.len();
+            users.retain(|user| user.uuid.to_string() != uuid);
+            let deleted = (count - users.len()) as u64;
+            Box::pin(async move { Ok(deleted) })
+        }
+
+        fn set_disabled<'a>(
+            &'a self,
+            uuid: &'a str,
+            is_disabled: bool,
+        ) -> BoxFuture<'a, Result<u64, DbError>> {
+            let updated = self.update(uuid, |user| user.is_disabled = is_disabled);
+            Box::pin(async move { Ok(updated) })
+        }
+
+        fn set_moderator<'a>(
+            &'a self,
+            uuid: &'a str,
+            is_moderator: bool,
+        ) -> BoxFuture<'a, Result<u64, DbError>> {
+            let updated = self.update(uuid, |user| user.is_moderator = is_moderator);
+            Box::pin(async move { Ok(updated) })
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use dash_types::chat::{ChatMessage, DEFAULT_ROOM};
+use email_address::EmailAddress;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::dialect::Dialect;
+use crate::error::DbError;
+use crate::pool::DbPool;
+use crate::repositories::user_repository::NewUser;
+use crate::strategies::chat_strategy::{current_timestamp, insert_message};
+use crate::strategies::user_strategy::{
+    get_user_by_username, get_user_by_username_or_email, hash_password, insert_user, set_user_flag,
+};
+use crate::transaction::transaction;
+use crate::user::connect;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedData {
+    #[serde(default)]
+    users: Vec<SeedUser>,
+    #[serde(default)]
+    messages: Vec<SeedMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedUser {
+    username: String,
+    email: String,
+    password: String,
+    #[serde(default)]
+    is_admin: bool,
+    #[serde(default)]
+    is_moderator: bool,
+    #[serde(default)]
+    is_disabled: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedMessage {
+    #[serde(default = "default_room")]
+    room: String,
+    username: String,
+    text: String,
+}
+
+#[derive(Clone)]
+struct PreparedUser {
+    user: NewUser,
+    is_moderator: bool,
+    is_disabled: bool,
+}
+
+fn default_room() -> String {
+    String::from(DEFAULT_ROOM)
+}
+
+fn parse_seed(path: &Path) -> Result<SeedData, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("");
+    let result = match extension.to_lowercase().as_str() {
+        "json" => serde_json::from_str(&content).map_err(|error| error.to_string()),
+        "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|error| error.to_string()),
+        _ => return Err(String::from("Seed file must have a .json, .yaml or .yml extension")),
+    };
+    result.map_err(|error| format!("Invalid seed file {}: {}", path.display(), error))
+}
+
+fn validate_seed(seed: &SeedData) -> Result<(), String> {
+    let mut usernames = HashSet::new();
+    for user in &seed.users {
+        if user.username.is_empty() || user.password.is_empty() {
+            return Err(format!("Seed user {:?} is missing a username or password", user.username));
+        }
+        if !EmailAddress::is_valid(&user.email) {
+            return Err(format!("Seed user {} has an invalid email", user.username));
+        }
+        if !usernames.insert(user.username.to_lowercase()) {
+            return Err(format!("Seed user {} is listed more than once", user.username));
+        }
+    }
+    for message in &seed.messages {
+        if message.room.is_empty() || message.text.trim().is_empty() {
+            return Err(format!(
+                "Seed message from {} is missing a room or text",
+                message.username
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn seed_database(
+    pool: &DbPool,
+    dialect: Dialect,
+    salt: [u8; 16],
+    seed: SeedData,
+) -> Result<(), String> {
+    let users: Vec<PreparedUser> = seed
+        .users
+        .into_iter()
+        .map(|user| PreparedUser {
+            user: NewUser {
+                uuid: Uuid::new_v4(),
+                username: user.username,
+                email: user.email,
+                password: hash_password(user.password, salt),
+                is_admin: user.is_admin,
+            },
+            is_moderator: user.is_moderator,
+            is_disabled: user.is_disabled,
+        })
+        .collect();
+    let messages = seed.messages;
+
+    let (created, skipped, sent) = transaction(pool, |transaction| {
+        let users = users.clone();
+        let messages = messages.clone();
+        Box::pin(async move {
+            let (mut created, mut skipped) = (0, 0);
+            for prepared in users {
+                let user = &prepared.user;
+                match get_user_by_username_or_email(&mut **transaction, dialect, &user.username)
+                    .await
+                {
+                    Ok(_) => {
+                        skipped += 1;
+                        continue;
+                    }
+                    Err(DbError::NotFound) => {}
+                    Err(error) => return Err(error),
+                }
+                match get_user_by_username_or_email(&mut **transaction, dialect, &user.email).await
+                {
+                    Ok(_) => {
+                        skipped += 1;
+                        continue;
+                    }
+                    Err(DbError::NotFound) => {}
+                    Err(error) => return Err(error),
+                }
+
+                let uuid = insert_user(&mut **transaction, dialect, user).await?.uuid.to_string();
+                if prepared.is_moderator {
+                    set_user_flag(&mut **transaction, dialect, "is_moderator", &uuid, true).await?;
+                }
+                if prepared.is_disabled {
+                    set_user_flag(&mut **transaction, dialect, "is_disabled", &uuid, true).await?;
+                }
+                created += 1;
+            }
+
+            let mut sent = 0;
+            for message in messages {
+                let user =
+                    get_user_by_username(&mut **transaction, dialect, &message.username).await?;
+                let message = ChatMessage {
+                    id: 0,
+                    room: message.room,
+                    user_uuid: user.uuid.to_string(),
+                    username: user.username,
+                    text: message.text,
+                    created_at: current_timestamp(),
+                    edited_at: None,
+                    deleted_at: None,
+                    attachments: Vec::new(),
+                };
+                insert_message(&mut **transaction, &message).await?;
+                sent += 1;
+            }
+            Ok((created, skipped, sent))
+        })
+    })
+    .await
+    .map_err(|error| match error {
+        DbError::NotFound => String::from("Seed message references a user that does not exist"),
+        error => error.to_string(),
+    })?;
+
+    println!("Seeded {} users ({} already existed) and {} messages", created, skipped, sent);
+    Ok(())
+}
+
+pub async fn command(config: &Config, args: &[String]) -> Result<(), String> {
+    let path = match args {
+        [path] => Path::new(path),
+        _ => return Err(String::from("Usage: dash_server seed <file.yaml|file.json>")),
+    };
+    let seed = parse_seed(path)?;
+    validate_seed(&seed)?;
+
+    let (pool, dialect) = connect(&config.database).await?;
+    seed_database(&pool, dialect, config.auth.password_salt, seed).await
+}
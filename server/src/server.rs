@@ -0,0 +1,172 @@
+use std::fmt::Debug;
+use std::future::IntoFuture;
+use std::io;
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::ws::close_code;
+use axum::serve::Listener;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tokio::time::{sleep, timeout};
+use tracing::{error, info, warn};
+
+use crate::config::ServerConfig;
+use crate::state::AppState;
+use crate::strategies::health_strategy::set_draining;
+use crate::strategies::session_strategy::Sessions;
+use crate::tls::TlsListener;
+
+const SESSION_DRAIN_POLL: Duration = Duration::from_millis(50);
+
+async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("Could not listen for SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Could not listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("Received SIGINT, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+}
+
+async fn drain_sessions(sessions: &Sessions) {
+    while sessions.count() > 0 {
+        sleep(SESSION_DRAIN_POLL).await;
+    }
+}
+
+async fn run<L>(
+    listener: L,
+    app: Router,
+    server: &ServerConfig,
+    sessions: &Sessions,
+) -> io::Result<()>
+where
+    L: Listener,
+    L::Addr: Debug,
+{
+    let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
+    let serving = axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            let _ = shutdown_rx.changed().await;
+        })
+        .into_future();
+    tokio::pin!(serving);
+
+    tokio::select! {
+        result = &mut serving => return result,
+        _ = shutdown_signal() => {}
+    }
+
+    set_draining();
+    if !server.shutdown_delay.is_zero() {
+        info!("Reporting not ready, still serving for {}s", server.shutdown_delay.as_secs());
+        tokio::select! {
+            result = &mut serving => return result,
+            _ = sleep(server.shutdown_delay) => {}
+        }
+    }
+
+    let shutdown_timeout = server.shutdown_timeout;
+    shutdown_tx.send_replace(true);
+    let open = sessions.disconnect_all(close_code::AWAY, "Server is shutting down");
+    info!("Closing {} open sessions, waiting up to {}s", open, shutdown_timeout.as_secs());
+
+    let drained = async {
+        let result = (&mut serving).await;
+        drain_sessions(sessions).await;
+        result
+    };
+    match timeout(shutdown_timeout, drained).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!("Shutdown deadline reached, dropping remaining connections");
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn run_unix(path: &std::path::Path, app: Router, server: &ServerConfig, sessions: &Sessions) {
+    use std::os::unix::fs::FileTypeExt;
+
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        let _ = std::fs::remove_file(path);
+    }
+    let listener = match tokio::net::UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(error) => panic!("Could not bind to {}: {}", path.display(), error),
+    };
+    info!("Server listening on unix:{}", path.display());
+    let result = run(listener, app, server, sessions).await;
+    let _ = std::fs::remove_file(path);
+    if let Err(error) = result {
+        error!(%error, "Server error");
+    }
+}
+
+pub async fn serve(server: &ServerConfig, app: Router, state: AppState) {
+    #[cfg(unix)]
+    if let Some(path) = &server.socket {
+        run_unix(path, app, server, &state.sessions).await;
+        close_pools(state).await;
+        return;
+    }
+
+    let address = format!("{}:{}", server.host, server.port);
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(error) => panic!("Could not bind to {}: {}", address, error),
+    };
+    let result = match &server.tls {
+        Some(tls) => {
+            let listener = match TlsListener::new(listener, tls) {
+                Ok(listener) => listener,
+                Err(error) => panic!("Could not configure TLS: {}", error),
+            };
+            info!("Server listening on https://{}", address);
+            run(listener, app, server, &state.sessions).await
+        }
+        None => {
+            info!("Server listening on http://{}", address);
+            run(listener, app, server, &state.sessions).await
+        }
+    };
+    if let Err(error) = result {
+        error!(%error, "Server error");
+    }
+    close_pools(state).await;
+}
+
+pub async fn serve_metrics(host: &str, port: u16, routes: Router) {
+    let address = format!("{}:{}", host, port);
+    let listener = match TcpListener::bind(&address).await {
+        Ok(listener) => listener,
+        Err(error) => panic!("Could not bind metrics to {}: {}", address, error),
+    };
+    info!("Metrics listening on http://{}/metrics", address);
+    tokio::spawn(async move {
+        if let Err(error) = axum::serve(listener, routes).await {
+            error!(%error, "Metrics server error");
+        }
+    });
+}
+
+async fn close_pools(state: AppState) {
+    state.pool.close().await;
+    if let Some(replica) = &state.replica {
+        replica.close().await;
+    }
+    info!("Closed database connections");
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+
+use crate::config::Config;
+use crate::controllers::ws_controller::WsState;
+use crate::pool::DbPool;
+use crate::pubsub::PubSub;
+use crate::repositories::user_repository::{SqlUserRepository, UserRepository};
+use crate::storage::Storage;
+use crate::strategies::realtime_strategy::RateLimits;
+use crate::strategies::session_strategy::Sessions;
+use crate::strategies::typing_strategy::Typing;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub pool: DbPool,
+    pub replica: Option<DbPool>,
+    pub users: Arc<dyn UserRepository>,
+    pub pubsub: Arc<dyn PubSub>,
+    pub storage: Arc<dyn Storage>,
+    pub ws: Arc<WsState>,
+    pub sessions: Arc<Sessions>,
+    pub typing: Arc<Typing>,
+    pub rate_limits: Arc<RateLimits>,
+}
+
+impl AppState {
+    pub fn new(
+        config: Arc<Config>,
+        pool: DbPool,
+        replica: Option<DbPool>,
+        pubsub: Arc<dyn PubSub>,
+        storage: Arc<dyn Storage>,
+    ) -> Self {
+        let read_pool = replica.clone().unwrap_or(pool.clone());
+        let dialect = config.database.dialect;
+        let users = Arc::new(SqlUserRepository::new(pool.clone(), read_pool, dialect));
+        Self {
+            config,
+            pool,
+            replica,
+            users,
+            pubsub,
+            storage,
+            ws: Arc::default(),
+            sessions: Arc::default(),
+            typing: Arc::default(),
+            rate_limits: Arc::default(),
+        }
+    }
+
+    pub fn read_pool(&self) -> &DbPool {
+        self.replica.as_ref().unwrap_or(&self.pool)
+    }
+}
+
+#[derive(Clone)]
+pub struct ReadPool(pub DbPool);
+
+impl FromRef<AppState> for ReadPool {
+    fn from_ref(state: &AppState) -> Self {
+        ReadPool(state.read_pool().clone())
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for DbPool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn UserRepository> {
+    fn from_ref(state: &AppState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn PubSub> {
+    fn from_ref(state: &AppState) -> Self {
+        state.pubsub.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Storage> {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<WsState> {
+    fn from_ref(state: &AppState) -> Self {
+        state.ws.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Typing> {
+    fn from_ref(state: &AppState) -> Self {
+        state.typing.clone()
+    }
+}
@@ -0,0 +1,234 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::StorageConfig;
+
+pub trait Storage: Send + Sync {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, io::Result<()>>;
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Vec<u8>>>;
+
+    fn exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<bool>>;
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        let is_valid = !key.is_empty()
+            && key.split('/').all(|part| !part.is_empty() && part != "." && part != "..");
+        if is_valid {
+            Ok(self.root.join(key))
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid key: {}", key)))
+        }
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            let partial = path.with_extension(format!("{}.partial", Uuid::new_v4()));
+            tokio::fs::write(&partial, data).await?;
+            tokio::fs::rename(partial, path).await
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Vec<u8>>> {
+        Box::pin(async move { tokio::fs::read(self.path(key)?).await })
+    }
+
+    fn exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<bool>> {
+        Box::pin(async move { tokio::fs::try_exists(self.path(key)?).await })
+    }
+}
+
+#[cfg(feature = "s3")]
+pub mod s3 {
+    use std::io;
+
+    use futures::future::BoxFuture;
+    use object_store::aws::{AmazonS3, AmazonS3Builder};
+    use object_store::path::Path;
+    use object_store::{ObjectStore, PutPayload};
+
+    use super::Storage;
+
+    pub struct S3Storage {
+        store: AmazonS3,
+    }
+
+    impl S3Storage {
+        pub fn from_env(bucket: &str) -> Result<Self, object_store::Error> {
+            let store = AmazonS3Builder::from_env().with_bucket_name(bucket).build()?;
+            Ok(Self { store })
+        }
+    }
+
+    fn to_io_error(error: object_store::Error) -> io::Error {
+        match error {
+            object_store::Error::NotFound { .. } => io::Error::new(io::ErrorKind::NotFound, error),
+            error => io::Error::other(error),
+        }
+    }
+
+    impl Storage for S3Storage {
+        fn put<'a>(&'a self, key: &'a str, data: Vec<u8>) -> BoxFuture<'a, io::Result<()>> {
+            Box::pin(async move {
+                self.store
+                    .put(&Path::from(key), PutPayload::from(data))
+                    .await
+                    .map(|_| ())
+                    .map_err(to_io_error)
+            })
+        }
+
+        fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<Vec<u8>>> {
+            Box::pin(async move {
+                let result = self.store.get(&Path::from(key)).await.map_err(to_io_error)?;
+                let bytes = result.bytes().await.map_err(to_io_error)?;
+                Ok(bytes.to_vec())
+            })
+        }
+
+        fn exists<'a>(&'a self, key: &'a str) -> BoxFuture<'a, io::Result<bool>> {
+            Box::pin(async move {
+                match self.store.head(&Path::from(key)).await {
+                    Ok(_) => Ok(true),
+                    Err(object_store::Error::NotFound { .. }) => Ok(false),
+                    Err(error) => Err(to_io_error(error)),
+                }
+            })
+        }
+    }
+}
+
+#[cfg(feature = "s3")]
+fn init_s3_storage(bucket: &str) -> Arc<dyn Storage> {
+    match s3::S3Storage::from_env(bucket) {
+        Ok(storage) => {
+            info!("Attachment storage using S3 bucket {}", bucket);
+            Arc::new(storage)
+        }
+        Err(error) => {
+            panic!("Could not create S3 storage: {}", error);
+        }
+    }
+}
+
+#[cfg(not(feature = "s3"))]
+fn init_s3_storage(_bucket: &str) -> Arc<dyn Storage> {
+    panic!("STORAGE_BACKEND=s3 requires the s3 feature");
+}
+
+pub fn create_storage(storage_config: &StorageConfig) -> Arc<dyn Storage> {
+    match storage_config.backend.as_str() {
+        "local" => {
+            let root = storage_config.dir.clone();
+            info!("Attachment storage using local directory {}", root.display());
+            Arc::new(LocalStorage::new(root))
+        }
+        "s3" => init_s3_storage(storage_config.s3_bucket.as_deref().unwrap_or_default()),
+        backend => panic!("Unknown STORAGE_BACKEND: {}", backend),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::repositories::user_repository::memory::InMemoryUserRepository;
+    use crate::state::AppState;
+    use crate::strategies::attachment_strategy::store_attachment;
+    use crate::test_utils::{test_pool, test_state};
+
+    fn local_storage() -> LocalStorage {
+        LocalStorage::new(env::temp_dir().join(Uuid::new_v4().to_string()))
+    }
+
+    async fn assert_round_trip(storage: &dyn Storage) {
+        let key = format!("tests/{}", Uuid::new_v4());
+        assert!(!storage.exists(&key).await.unwrap());
+        assert_eq!(storage.get(&key).await.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        storage.put(&key, b"hello".to_vec()).await.unwrap();
+        assert!(storage.exists(&key).await.unwrap());
+        assert_eq!(storage.get(&key).await.unwrap(), b"hello");
+    }
+
+    async fn assert_deduplicates(storage: Arc<dyn Storage>) {
+        let state = AppState {
+            pool: test_pool("sqlite::memory:").await,
+            storage,
+            ..test_state(Arc::new(InMemoryUserRepository::default()))
+        };
+        let uuid = Uuid::new_v4().to_string();
+        let data = Uuid::new_v4().to_string().into_bytes();
+
+        let first = store_attachment(&state, &uuid, "a.txt", "text/plain", data.clone()).await;
+        let second = store_attachment(&state, &uuid, "b.txt", "text/plain", data.clone()).await;
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.storage_key, second.storage_key);
+        assert_eq!(state.storage.get(&first.storage_key).await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn local_round_trip() {
+        assert_round_trip(&local_storage()).await;
+    }
+
+    #[tokio::test]
+    async fn local_deduplicates() {
+        assert_deduplicates(Arc::new(local_storage())).await;
+    }
+
+    #[tokio::test]
+    async fn local_rejects_path_traversal() {
+        let storage = local_storage();
+        for key in ["", "../escape", "blobs/../../escape", "/etc/passwd", "blobs//key", "./key"] {
+            let error = storage.put(key, b"x".to_vec()).await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{}", key);
+            assert!(storage.get(key).await.is_err());
+            assert!(storage.exists(key).await.is_err());
+        }
+    }
+
+    #[cfg(feature = "s3")]
+    fn s3_storage() -> s3::S3Storage {
+        env::var("AWS_ENDPOINT").expect("AWS_ENDPOINT is not set");
+        let bucket = env::var("TEST_S3_BUCKET").expect("TEST_S3_BUCKET is not set");
+        s3::S3Storage::from_env(&bucket).unwrap()
+    }
+
+    #[cfg(feature = "s3")]
+    #[tokio::test]
+    #[ignore = "requires an S3 endpoint in AWS_ENDPOINT and a bucket in TEST_S3_BUCKET"]
+    async fn s3_round_trip() {
+        assert_round_trip(&s3_storage()).await;
+    }
+
+    #[cfg(feature = "s3")]
+    #[tokio::test]
+    #[ignore = "requires an S3 endpoint in AWS_ENDPOINT and a bucket in TEST_S3_BUCKET"]
+    async fn s3_deduplicates() {
+        assert_deduplicates(Arc::new(s3_storage())).await;
+    }
+}
@@ -0,0 +1,311 @@
+use std::io::Cursor;
+
+use dash_types::attachment::{Attachment, AttachmentInfo};
+use dash_types::chat::{ChatMessage, SanctionKind};
+use dash_types::error::{ApiErrorCode, FieldError};
+use hmac::{Hmac, Mac};
+use image::{DynamicImage, ImageFormat, ImageReader, Limits};
+use sha2::{Digest, Sha256};
+use sqlx::any::AnyQueryResult;
+use sqlx::{Any, Executor};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::config::AttachmentConfig;
+use crate::error::ApiError;
+use crate::pool::DbPool;
+use crate::state::AppState;
+use crate::strategies::chat_strategy::{current_timestamp, get_message_by_id, has_active_sanction};
+
+const THUMBNAIL_SIZE: u32 = 256;
+const MAX_IMAGE_DIMENSION: u32 = 16384;
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+pub async fn insert_attachment<'e, E>(
+    executor: E,
+    attachment: &Attachment,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"attachments\" (id, uploader_uuid, message_id, file_name,
+        content_type, size, hash, storage_key, thumbnail_key, width, height, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12);";
+    sqlx::query(query)
+        .bind(&attachment.id)
+        .bind(&attachment.uploader_uuid)
+        .bind(attachment.message_id)
+        .bind(&attachment.file_name)
+        .bind(&attachment.content_type)
+        .bind(attachment.size)
+        .bind(&attachment.hash)
+        .bind(&attachment.storage_key)
+        .bind(&attachment.thumbnail_key)
+        .bind(attachment.width)
+        .bind(attachment.height)
+        .bind(attachment.created_at)
+        .execute(executor)
+        .await
+}
+
+pub async fn get_attachment_by_id<'e, E>(executor: E, id: &str) -> Result<Attachment, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT * FROM \"attachments\" WHERE id = $1;";
+    sqlx::query_as::<_, Attachment>(query).bind(id).fetch_one(executor).await
+}
+
+async fn get_attachment_by_hash<'e, E>(
+    executor: E,
+    hash: &str,
+) -> Result<Option<Attachment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT * FROM \"attachments\" WHERE hash = $1 ORDER BY created_at LIMIT 1;";
+    sqlx::query_as::<_, Attachment>(query).bind(hash).fetch_optional(executor).await
+}
+
+pub async fn link_attachment<'e, E>(
+    executor: E,
+    id: &str,
+    uploader_uuid: &str,
+    message_id: i64,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "UPDATE \"attachments\" SET message_id = $1
+        WHERE id = $2 AND uploader_uuid = $3 AND message_id IS NULL;";
+    sqlx::query(query).bind(message_id).bind(id).bind(uploader_uuid).execute(executor).await
+}
+
+async fn get_attachments_by_message_range<'e, E>(
+    executor: E,
+    first_id: i64,
+    last_id: i64,
+) -> Result<Vec<Attachment>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT * FROM \"attachments\"
+        WHERE message_id >= $1 AND message_id <= $2
+        ORDER BY created_at;";
+    sqlx::query_as::<_, Attachment>(query).bind(first_id).bind(last_id).fetch_all(executor).await
+}
+
+fn sign(config: &AttachmentConfig, id: &str, expires: i64, is_thumbnail: bool) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(config.url_secret.as_bytes()).unwrap();
+    mac.update(format!("{}:{}:{}", id, expires, is_thumbnail).as_bytes());
+    mac
+}
+
+pub fn verify_signature(
+    config: &AttachmentConfig,
+    id: &str,
+    expires: i64,
+    is_thumbnail: bool,
+    signature: &str,
+) -> bool {
+    if expires < current_timestamp() {
+        return false;
+    }
+
+    match hex::decode(signature) {
+        Ok(signature) => sign(config, id, expires, is_thumbnail).verify_slice(&signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn signed_url(config: &AttachmentConfig, id: &str, expires: i64, is_thumbnail: bool) -> String {
+    let signature = hex::encode(sign(config, id, expires, is_thumbnail).finalize().into_bytes());
+    let thumbnail = if is_thumbnail { "&thumbnail=true" } else { "" };
+    format!("/attachments/{}?expires={}&signature={}{}", id, expires, signature, thumbnail)
+}
+
+pub fn to_attachment_info(config: &AttachmentConfig, attachment: Attachment) -> AttachmentInfo {
+    let expires_at = current_timestamp() + config.url_expiry as i64;
+    AttachmentInfo {
+        url: signed_url(config, &attachment.id, expires_at, false),
+        thumbnail_url: attachment
+            .thumbnail_key
+            .as_ref()
+            .map(|_| signed_url(config, &attachment.id, expires_at, true)),
+        id: attachment.id,
+        file_name: attachment.file_name,
+        content_type: attachment.content_type,
+        size: attachment.size,
+        width: attachment.width,
+        height: attachment.height,
+        expires_at,
+    }
+}
+
+pub async fn load_attachments(
+    pool: &DbPool,
+    config: &AttachmentConfig,
+    messages: &mut [ChatMessage],
+) -> Result<(), sqlx::Error> {
+    let (Some(first), Some(last)) = (
+        messages.iter().map(|message| message.id).min(),
+        messages.iter().map(|message| message.id).max(),
+    ) else {
+        return Ok(());
+    };
+
+    for attachment in get_attachments_by_message_range(pool, first, last).await? {
+        let message = messages.iter_mut().find(|message| Some(message.id) == attachment.message_id);
+        if let Some(message) = message {
+            message.attachments.push(to_attachment_info(config, attachment));
+        }
+    }
+    Ok(())
+}
+
+pub async fn can_access_attachment(
+    pool: &DbPool,
+    user_uuid: &str,
+    attachment: &Attachment,
+) -> Result<bool, sqlx::Error> {
+    if attachment.uploader_uuid == user_uuid {
+        return Ok(true);
+    }
+
+    let Some(message_id) = attachment.message_id else {
+        return Ok(false);
+    };
+    let message = match get_message_by_id(pool, message_id).await {
+        Ok(message) => message,
+        Err(sqlx::Error::RowNotFound) => return Ok(false),
+        Err(error) => return Err(error),
+    };
+    Ok(!has_active_sanction(pool, user_uuid, Some(&message.room), SanctionKind::Ban).await?)
+}
+
+fn sanitize_file_name(file_name: &str) -> String {
+    let file_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let file_name: String = file_name
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+    if file_name.trim().is_empty() { String::from("attachment") } else { file_name }
+}
+
+fn detect_content_type(types: &[String], declared: &str, data: &[u8]) -> Result<String, String> {
+    let declared = declared.split(';').next().unwrap_or_default().trim().to_lowercase();
+    let content_type = match image::guess_format(data) {
+        Ok(format) => format.to_mime_type().to_string(),
+        Err(_) if declared.starts_with("image/") => {
+            return Err(String::from("File content does not match its type"));
+        }
+        Err(_) => declared,
+    };
+
+    if types.contains(&content_type) {
+        Ok(content_type)
+    } else {
+        Err(format!("File type not allowed: {}", content_type))
+    }
+}
+
+fn create_thumbnail(data: &[u8]) -> Result<(Vec<u8>, u32, u32), String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|error| error.to_string())?;
+    reader.limits(limits);
+    let image = reader.decode().map_err(|error| error.to_string())?;
+
+    let thumbnail =
+        DynamicImage::ImageRgba8(image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).into_rgba8());
+    let mut bytes = Vec::new();
+    thumbnail
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|error| error.to_string())?;
+    Ok((bytes, image.width(), image.height()))
+}
+
+pub fn invalid_file(message: &str) -> ApiError {
+    ApiError::validation(vec![FieldError::new("file", message)])
+}
+
+fn storage_error(error: std::io::Error) -> ApiError {
+    error!(%error, "Error accessing attachment storage");
+    ApiError::from_code(ApiErrorCode::ServerError)
+}
+
+pub async fn store_attachment(
+    state: &AppState,
+    uploader_uuid: &str,
+    file_name: &str,
+    declared_type: &str,
+    data: Vec<u8>,
+) -> Result<Attachment, ApiError> {
+    let max_size = state.config.attachments.max_size;
+    if data.is_empty() || data.len() > max_size {
+        let message = format!("Attachments must be between 1 and {} bytes", max_size);
+        return Err(invalid_file(&message));
+    }
+    let content_type = detect_content_type(&state.config.attachments.types, declared_type, &data)
+        .map_err(|message| invalid_file(&message))?;
+
+    let size = data.len() as i64;
+    let hash = hex::encode(Sha256::digest(&data));
+    let storage = &state.storage;
+    let existing = get_attachment_by_hash(&state.pool, &hash).await?;
+    let (storage_key, thumbnail_key, width, height) = match existing {
+        Some(existing) if storage.exists(&existing.storage_key).await.map_err(storage_error)? => {
+            (existing.storage_key, existing.thumbnail_key, existing.width, existing.height)
+        }
+        _ => {
+            let storage_key = format!("blobs/{}/{}", &hash[..2], hash);
+            let thumbnail = if content_type.starts_with("image/") {
+                let image_data = data.clone();
+                match tokio::task::spawn_blocking(move || create_thumbnail(&image_data)).await {
+                    Ok(Ok(thumbnail)) => Some(thumbnail),
+                    Ok(Err(message)) => {
+                        return Err(invalid_file(&message));
+                    }
+                    Err(error) => {
+                        warn!(%error, "Error creating thumbnail");
+                        return Err(ApiError::from_code(ApiErrorCode::ServerError));
+                    }
+                }
+            } else {
+                None
+            };
+
+            storage.put(&storage_key, data).await.map_err(storage_error)?;
+            match thumbnail {
+                Some((thumbnail, width, height)) => {
+                    let thumbnail_key = format!("thumbnails/{}/{}.png", &hash[..2], hash);
+                    storage.put(&thumbnail_key, thumbnail).await.map_err(storage_error)?;
+                    (storage_key, Some(thumbnail_key), Some(width as i32), Some(height as i32))
+                }
+                None => (storage_key, None, None, None),
+            }
+        }
+    };
+
+    let attachment = Attachment {
+        id: Uuid::new_v4().to_string(),
+        uploader_uuid: uploader_uuid.to_string(),
+        message_id: None,
+        file_name: sanitize_file_name(file_name),
+        content_type,
+        size,
+        hash,
+        storage_key,
+        thumbnail_key,
+        width,
+        height,
+        created_at: current_timestamp(),
+    };
+    insert_attachment(&state.pool, &attachment).await?;
+    Ok(attachment)
+}
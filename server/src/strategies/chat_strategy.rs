@@ -0,0 +1,301 @@
+use dash_types::chat::{ChatMessage, ModerationAction, ReactionCount, ReadMarker, SanctionKind};
+use jsonwebtoken::get_current_timestamp;
+use sqlx::any::AnyQueryResult;
+use sqlx::{Any, Executor};
+use uuid::Uuid;
+
+pub fn current_timestamp() -> i64 {
+    get_current_timestamp() as i64
+}
+
+pub async fn insert_message<'e, E>(executor: E, message: &ChatMessage) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"messages\" (room, user_uuid, username, text, created_at)
+        VALUES ($1, $2, $3, $4, $5) RETURNING id;";
+    sqlx::query_scalar(query)
+        .bind(&message.room)
+        .bind(&message.user_uuid)
+        .bind(&message.username)
+        .bind(&message.text)
+        .bind(message.created_at)
+        .fetch_one(executor)
+        .await
+}
+
+pub async fn get_message_by_id<'e, E>(executor: E, id: i64) -> Result<ChatMessage, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT * FROM \"messages\" WHERE id = $1 AND deleted_at IS NULL;";
+    sqlx::query_as::<_, ChatMessage>(query).bind(id).fetch_one(executor).await
+}
+
+pub async fn get_messages<'e, E>(
+    executor: E,
+    room: &str,
+    after: Option<i64>,
+    limit: i64,
+) -> Result<Vec<ChatMessage>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    match after {
+        Some(after) => {
+            let query = "SELECT * FROM \"messages\"
+                WHERE room = $1 AND id > $2 AND deleted_at IS NULL
+                ORDER BY id ASC LIMIT $3;";
+            sqlx::query_as::<_, ChatMessage>(query)
+                .bind(room)
+                .bind(after)
+                .bind(limit)
+                .fetch_all(executor)
+                .await
+        }
+        None => {
+            let query = "SELECT * FROM \"messages\"
+                WHERE room = $1 AND deleted_at IS NULL
+                ORDER BY id DESC LIMIT $2;";
+            let mut messages = sqlx::query_as::<_, ChatMessage>(query)
+                .bind(room)
+                .bind(limit)
+                .fetch_all(executor)
+                .await?;
+            messages.reverse();
+            Ok(messages)
+        }
+    }
+}
+
+pub async fn update_message_text<'e, E>(
+    executor: E,
+    id: i64,
+    text: &str,
+    edited_at: i64,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "UPDATE \"messages\" SET text = $1, edited_at = $2
+        WHERE id = $3 AND deleted_at IS NULL;";
+    sqlx::query(query).bind(text).bind(edited_at).bind(id).execute(executor).await
+}
+
+pub async fn delete_message<'e, E>(
+    executor: E,
+    id: i64,
+    deleted_at: i64,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "UPDATE \"messages\" SET deleted_at = $1 WHERE id = $2 AND deleted_at IS NULL;";
+    sqlx::query(query).bind(deleted_at).bind(id).execute(executor).await
+}
+
+pub async fn upsert_read_marker<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    room: &str,
+    message_id: i64,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"read_markers\" (user_uuid, room, message_id, updated_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_uuid, room) DO UPDATE
+        SET message_id = excluded.message_id, updated_at = excluded.updated_at
+        WHERE excluded.message_id > read_markers.message_id;";
+    sqlx::query(query)
+        .bind(user_uuid)
+        .bind(room)
+        .bind(message_id)
+        .bind(current_timestamp())
+        .execute(executor)
+        .await
+}
+
+pub async fn get_read_markers<'e, E>(
+    executor: E,
+    room: &str,
+) -> Result<Vec<ReadMarker>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT * FROM \"read_markers\" WHERE room = $1;";
+    sqlx::query_as::<_, ReadMarker>(query).bind(room).fetch_all(executor).await
+}
+
+pub async fn insert_reaction<'e, E>(
+    executor: E,
+    message_id: i64,
+    user_uuid: &str,
+    emoji: &str,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"message_reactions\" (message_id, user_uuid, emoji, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (message_id, user_uuid, emoji) DO NOTHING;";
+    sqlx::query(query)
+        .bind(message_id)
+        .bind(user_uuid)
+        .bind(emoji)
+        .bind(current_timestamp())
+        .execute(executor)
+        .await
+}
+
+pub async fn delete_reaction<'e, E>(
+    executor: E,
+    message_id: i64,
+    user_uuid: &str,
+    emoji: &str,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "DELETE FROM \"message_reactions\"
+        WHERE message_id = $1 AND user_uuid = $2 AND emoji = $3;";
+    sqlx::query(query).bind(message_id).bind(user_uuid).bind(emoji).execute(executor).await
+}
+
+pub async fn get_reaction_counts<'e, E>(
+    executor: E,
+    message_id: i64,
+) -> Result<Vec<ReactionCount>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT emoji, COUNT(*) AS count FROM \"message_reactions\"
+        WHERE message_id = $1
+        GROUP BY emoji
+        ORDER BY MIN(created_at), emoji;";
+    sqlx::query_as::<_, ReactionCount>(query).bind(message_id).fetch_all(executor).await
+}
+
+pub async fn insert_sanction<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    room: Option<&str>,
+    kind: SanctionKind,
+    expires_at: Option<i64>,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"chat_sanctions\" (id, user_uuid, room, kind, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6);";
+    sqlx::query(query)
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_uuid)
+        .bind(room)
+        .bind(kind.as_str())
+        .bind(expires_at)
+        .bind(current_timestamp())
+        .execute(executor)
+        .await
+}
+
+pub async fn delete_sanctions<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    room: Option<&str>,
+    kind: SanctionKind,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    match room {
+        Some(room) => {
+            let query = "DELETE FROM \"chat_sanctions\"
+                WHERE user_uuid = $1 AND kind = $2 AND room = $3;";
+            sqlx::query(query)
+                .bind(user_uuid)
+                .bind(kind.as_str())
+                .bind(room)
+                .execute(executor)
+                .await
+        }
+        None => {
+            let query = "DELETE FROM \"chat_sanctions\"
+                WHERE user_uuid = $1 AND kind = $2 AND room IS NULL;";
+            sqlx::query(query).bind(user_uuid).bind(kind.as_str()).execute(executor).await
+        }
+    }
+}
+
+pub async fn has_active_sanction<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    room: Option<&str>,
+    kind: SanctionKind,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT COUNT(*) FROM \"chat_sanctions\"
+        WHERE user_uuid = $1 AND kind = $2
+        AND (room IS NULL OR room = $3)
+        AND (expires_at IS NULL OR expires_at > $4);";
+    let count: i64 = sqlx::query_scalar(query)
+        .bind(user_uuid)
+        .bind(kind.as_str())
+        .bind(room)
+        .bind(current_timestamp())
+        .fetch_one(executor)
+        .await?;
+    Ok(count > 0)
+}
+
+pub async fn insert_moderation_action<'e, E>(
+    executor: E,
+    action: &ModerationAction,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"moderation_actions\"
+        (id, moderator_uuid, target_uuid, action, room, message_id, reason, expires_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9);";
+    sqlx::query(query)
+        .bind(&action.id)
+        .bind(&action.moderator_uuid)
+        .bind(&action.target_uuid)
+        .bind(&action.action)
+        .bind(&action.room)
+        .bind(action.message_id)
+        .bind(&action.reason)
+        .bind(action.expires_at)
+        .bind(action.created_at)
+        .execute(executor)
+        .await
+}
+
+pub async fn get_moderation_actions<'e, E>(
+    executor: E,
+    target_uuid: Option<&str>,
+    limit: i64,
+) -> Result<Vec<ModerationAction>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    match target_uuid {
+        Some(target_uuid) => {
+            let query = "SELECT * FROM \"moderation_actions\"
+                WHERE target_uuid = $1 ORDER BY created_at DESC LIMIT $2;";
+            sqlx::query_as::<_, ModerationAction>(query)
+                .bind(target_uuid)
+                .bind(limit)
+                .fetch_all(executor)
+                .await
+        }
+        None => {
+            let query = "SELECT * FROM \"moderation_actions\" ORDER BY created_at DESC LIMIT $1;";
+            sqlx::query_as::<_, ModerationAction>(query).bind(limit).fetch_all(executor).await
+        }
+    }
+}
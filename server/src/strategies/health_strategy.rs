@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use dash_types::health::{HealthCheck, HealthReport, HealthStatus};
+use tokio::time::timeout;
+
+use crate::migrate::{check_migrations, get_migrator};
+use crate::pool::DbPool;
+use crate::state::AppState;
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+pub fn set_draining() {
+    DRAINING.store(true, Ordering::Relaxed);
+}
+
+fn is_draining() -> bool {
+    DRAINING.load(Ordering::Relaxed)
+}
+
+async fn run_check(name: &str, check: impl Future<Output = Result<(), String>>) -> HealthCheck {
+    let started = Instant::now();
+    let result = match timeout(HEALTH_CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(format!("Timed out after {}s", HEALTH_CHECK_TIMEOUT.as_secs())),
+    };
+    HealthCheck {
+        name: name.to_string(),
+        status: if result.is_ok() { HealthStatus::Pass } else { HealthStatus::Fail },
+        latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+        error: result.err(),
+    }
+}
+
+async fn ping(pool: &DbPool) -> Result<(), String> {
+    sqlx::query("SELECT 1").execute(pool).await.map(|_| ()).map_err(|error| error.to_string())
+}
+
+fn report(checks: Vec<HealthCheck>) -> HealthReport {
+    let status = if checks.iter().all(|check| check.status == HealthStatus::Pass) {
+        HealthStatus::Pass
+    } else {
+        HealthStatus::Fail
+    };
+    HealthReport { status, checks }
+}
+
+pub fn liveness() -> HealthReport {
+    report(Vec::new())
+}
+
+pub async fn readiness(state: &AppState) -> HealthReport {
+    let mut checks = Vec::new();
+    checks.push(run_check("config", async { Ok(()) }).await);
+    checks.push(run_check("database", ping(&state.pool)).await);
+    if let Some(replica) = &state.replica {
+        checks.push(run_check("replica", ping(replica)).await);
+    }
+    let database = &state.config.database;
+    if database.migrations != "off" {
+        let migrator = get_migrator(database.dialect);
+        checks.push(run_check("migrations", check_migrations(&state.pool, migrator)).await);
+    }
+    checks.push(
+        run_check("shutdown", async {
+            if is_draining() { Err(String::from("Server is shutting down")) } else { Ok(()) }
+        })
+        .await,
+    );
+    report(checks)
+}
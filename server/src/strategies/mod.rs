@@ -1,2 +1,9 @@
+pub mod attachment_strategy;
 pub mod auth_strategy;
+pub mod chat_strategy;
+pub mod health_strategy;
+pub mod notification_strategy;
+pub mod realtime_strategy;
+pub mod session_strategy;
+pub mod typing_strategy;
 pub mod user_strategy;
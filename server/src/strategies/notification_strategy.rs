@@ -0,0 +1,229 @@
+use dash_types::notification::{Notification, NotificationCategory, NotificationPreference};
+use dash_types::ws::WsServerMessage;
+use sqlx::any::AnyQueryResult;
+use sqlx::{Any, Executor};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::state::AppState;
+use crate::strategies::chat_strategy::current_timestamp;
+
+pub struct NewNotification {
+    pub category: NotificationCategory,
+    pub title: String,
+    pub body: String,
+    pub room: Option<String>,
+    pub message_id: Option<i64>,
+}
+
+impl NewNotification {
+    pub fn new(category: NotificationCategory, title: &str, body: String) -> Self {
+        Self { category, title: title.to_string(), body, room: None, message_id: None }
+    }
+}
+
+pub async fn insert_notification<'e, E>(
+    executor: E,
+    notification: &Notification,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "INSERT INTO \"notifications\"
+        (id, user_uuid, category, title, body, room, message_id, read_at, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9);";
+    sqlx::query(query)
+        .bind(&notification.id)
+        .bind(&notification.user_uuid)
+        .bind(&notification.category)
+        .bind(&notification.title)
+        .bind(&notification.body)
+        .bind(&notification.room)
+        .bind(notification.message_id)
+        .bind(notification.read_at)
+        .bind(notification.created_at)
+        .execute(executor)
+        .await
+}
+
+pub async fn get_notifications<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    unread_only: bool,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<Notification>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = if unread_only {
+        "SELECT * FROM \"notifications\"
+            WHERE user_uuid = $1 AND created_at < $2 AND read_at IS NULL
+            ORDER BY created_at DESC, id DESC LIMIT $3;"
+    } else {
+        "SELECT * FROM \"notifications\"
+            WHERE user_uuid = $1 AND created_at < $2
+            ORDER BY created_at DESC, id DESC LIMIT $3;"
+    };
+    sqlx::query_as::<_, Notification>(query)
+        .bind(user_uuid)
+        .bind(before.unwrap_or(i64::MAX))
+        .bind(limit)
+        .fetch_all(executor)
+        .await
+}
+
+pub async fn count_unread_notifications<'e, E>(
+    executor: E,
+    user_uuid: &str,
+) -> Result<i64, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT COUNT(*) FROM \"notifications\" WHERE user_uuid = $1 AND read_at IS NULL;";
+    sqlx::query_scalar(query).bind(user_uuid).fetch_one(executor).await
+}
+
+pub async fn mark_notification_read<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    id: &str,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "UPDATE \"notifications\" SET read_at = COALESCE(read_at, $1)
+        WHERE id = $2 AND user_uuid = $3;";
+    sqlx::query(query).bind(current_timestamp()).bind(id).bind(user_uuid).execute(executor).await
+}
+
+pub async fn mark_all_notifications_read<'e, E>(
+    executor: E,
+    user_uuid: &str,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "UPDATE \"notifications\" SET read_at = $1
+        WHERE user_uuid = $2 AND read_at IS NULL;";
+    sqlx::query(query).bind(current_timestamp()).bind(user_uuid).execute(executor).await
+}
+
+pub async fn delete_notification<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    id: &str,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "DELETE FROM \"notifications\" WHERE id = $1 AND user_uuid = $2;";
+    sqlx::query(query).bind(id).bind(user_uuid).execute(executor).await
+}
+
+async fn get_opt_outs<'e, E>(executor: E, user_uuid: &str) -> Result<Vec<String>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT category FROM \"notification_opt_outs\" WHERE user_uuid = $1;";
+    sqlx::query_scalar(query).bind(user_uuid).fetch_all(executor).await
+}
+
+pub async fn get_notification_preferences<'e, E>(
+    executor: E,
+    user_uuid: &str,
+) -> Result<Vec<NotificationPreference>, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let opt_outs = get_opt_outs(executor, user_uuid).await?;
+    Ok(NotificationCategory::ALL
+        .into_iter()
+        .map(|category| NotificationPreference {
+            category,
+            enabled: !opt_outs.iter().any(|opt_out| opt_out == category.as_str()),
+        })
+        .collect())
+}
+
+pub async fn set_notification_preference<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    preference: &NotificationPreference,
+) -> Result<AnyQueryResult, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    if preference.enabled {
+        let query = "DELETE FROM \"notification_opt_outs\" WHERE user_uuid = $1 AND category = $2;";
+        sqlx::query(query)
+            .bind(user_uuid)
+            .bind(preference.category.as_str())
+            .execute(executor)
+            .await
+    } else {
+        let query = "INSERT INTO \"notification_opt_outs\" (user_uuid, category, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_uuid, category) DO NOTHING;";
+        sqlx::query(query)
+            .bind(user_uuid)
+            .bind(preference.category.as_str())
+            .bind(current_timestamp())
+            .execute(executor)
+            .await
+    }
+}
+
+async fn is_opted_out<'e, E>(
+    executor: E,
+    user_uuid: &str,
+    category: NotificationCategory,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = "SELECT COUNT(*) FROM \"notification_opt_outs\"
+        WHERE user_uuid = $1 AND category = $2;";
+    let count: i64 = sqlx::query_scalar(query)
+        .bind(user_uuid)
+        .bind(category.as_str())
+        .fetch_one(executor)
+        .await?;
+    Ok(count > 0)
+}
+
+pub async fn create_notification(
+    state: &AppState,
+    user_uuid: &str,
+    new_notification: NewNotification,
+) -> Result<Option<Notification>, sqlx::Error> {
+    let NewNotification { category, title, body, room, message_id } = new_notification;
+    if is_opted_out(&state.pool, user_uuid, category).await? {
+        return Ok(None);
+    }
+
+    let notification = Notification {
+        id: Uuid::new_v4().to_string(),
+        user_uuid: user_uuid.to_string(),
+        category: category.as_str().to_string(),
+        title,
+        body,
+        room,
+        message_id,
+        read_at: None,
+        created_at: current_timestamp(),
+    };
+    insert_notification(&state.pool, &notification).await?;
+    state.pubsub.publish(WsServerMessage::Notification(notification.clone()));
+    Ok(Some(notification))
+}
+
+pub fn notify(state: &AppState, user_uuid: String, new_notification: NewNotification) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        let category = new_notification.category.as_str();
+        if let Err(error) = create_notification(&state, &user_uuid, new_notification).await {
+            error!(user_uuid, %error, "Error creating {} notification", category);
+        }
+    });
+}
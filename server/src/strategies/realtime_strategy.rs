@@ -0,0 +1,846 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::prelude::*;
+use dash_types::attachment::Attachment;
+use dash_types::chat::{
+    ChatMessage, DEFAULT_ROOM, ModerationAction, ModerationActionType, SanctionKind,
+};
+use dash_types::error::ApiErrorCode;
+use dash_types::notification::NotificationCategory;
+use dash_types::user::User;
+use dash_types::ws::{CLOSE_BANNED, CLOSE_KICKED, WsClientMessage, WsServerMessage};
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Instant, interval};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::config::{Config, WsConfig};
+use crate::error::{ApiError, DbError};
+use crate::metrics::record_auth_outcome;
+use crate::pool::DbPool;
+use crate::pubsub::PubSub;
+use crate::state::AppState;
+use crate::strategies::attachment_strategy::{
+    get_attachment_by_id, link_attachment, load_attachments, to_attachment_info,
+};
+use crate::strategies::auth_strategy::{AuthRequestClaims, JWTClaims};
+use crate::strategies::chat_strategy::{
+    current_timestamp, delete_message, delete_reaction, delete_sanctions, get_message_by_id,
+    get_messages, get_reaction_counts, has_active_sanction, insert_message,
+    insert_moderation_action, insert_reaction, insert_sanction, update_message_text,
+    upsert_read_marker,
+};
+use crate::strategies::notification_strategy::{NewNotification, create_notification, notify};
+use crate::strategies::typing_strategy::Typing;
+use crate::telemetry::record_user;
+use crate::transaction::transaction;
+
+const MAX_ROOM_LENGTH: usize = 64;
+const MAX_MESSAGE_LENGTH: usize = 4000;
+const MAX_EMOJI_LENGTH: usize = 32;
+const TYPING_THROTTLE: Duration = Duration::from_secs(3);
+const TYPING_EXPIRY: u64 = 6;
+const MAX_ATTACHMENTS: usize = 10;
+const MAX_MENTIONS: usize = 10;
+const MENTION_PREVIEW_LENGTH: usize = 200;
+
+pub enum Credential {
+    Token(String),
+    Ticket(String),
+}
+
+pub struct Connection {
+    pub state: AppState,
+    pub user: User,
+    pub rooms: HashSet<String>,
+    typing: HashMap<String, Instant>,
+}
+
+impl Connection {
+    pub fn new(state: AppState, user: User) -> Self {
+        Self { state, user, rooms: HashSet::new(), typing: HashMap::new() }
+    }
+}
+
+#[derive(Default)]
+pub struct RateLimits {
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimits {
+    fn check(&self, config: &WsConfig, uuid: &str) -> bool {
+        let window = Duration::from_secs(config.rate_window);
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+        let (start, count) = windows.entry(uuid.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= window {
+            (*start, *count) = (now, 0);
+        }
+        *count += 1;
+        *count <= config.rate_limit
+    }
+
+    fn prune(&self, window: Duration) {
+        let now = Instant::now();
+        self.windows.lock().unwrap().retain(|_, (start, _)| now.duration_since(*start) < window);
+    }
+}
+
+fn hash_ticket(ticket: &str) -> String {
+    hex::encode(Sha256::digest(ticket.as_bytes()))
+}
+
+pub async fn issue_ticket(state: &AppState, uuid: &str, exp: u64) -> Result<String, sqlx::Error> {
+    let mut bytes = [0u8; 32];
+    rand::fill(&mut bytes);
+    let ticket = BASE64_URL_SAFE_NO_PAD.encode(bytes);
+
+    let now = current_timestamp();
+    let query = "DELETE FROM \"ws_tickets\" WHERE expires_at <= $1;";
+    sqlx::query(query).bind(now).execute(&state.pool).await?;
+
+    let query = "INSERT INTO \"ws_tickets\" (ticket_hash, user_uuid, token_exp, expires_at)
+        VALUES ($1, $2, $3, $4);";
+    sqlx::query(query)
+        .bind(hash_ticket(&ticket))
+        .bind(uuid)
+        .bind(exp as i64)
+        .bind(now + state.config.ws.ticket_expiry as i64)
+        .execute(&state.pool)
+        .await?;
+    Ok(ticket)
+}
+
+async fn redeem_ticket(pool: &DbPool, ticket: &str) -> Result<Option<(String, u64)>, sqlx::Error> {
+    let query = "DELETE FROM \"ws_tickets\" WHERE ticket_hash = $1
+        RETURNING user_uuid, token_exp, expires_at;";
+    let auth: Option<(String, i64, i64)> =
+        sqlx::query_as(query).bind(hash_ticket(ticket)).fetch_optional(pool).await?;
+    Ok(auth
+        .filter(|(_, _, expires_at)| *expires_at > current_timestamp())
+        .map(|(uuid, exp, _)| (uuid, exp as u64)))
+}
+
+pub async fn get_active_user(state: &AppState, uuid: &str) -> Result<User, ApiError> {
+    let user = match state.users.get_by_uuid(uuid).await {
+        Ok(user) if user.is_disabled => {
+            return Err(ApiError::from_code(ApiErrorCode::UserDisabled));
+        }
+        Ok(user) => user,
+        Err(DbError::NotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::Unauthorized));
+        }
+        Err(error) => return Err(ApiError::from(error)),
+    };
+
+    match has_active_sanction(&state.pool, &user.uuid.to_string(), None, SanctionKind::Ban).await {
+        Ok(false) => Ok(user),
+        Ok(true) => Err(ApiError::from_code(ApiErrorCode::AccessDenied)),
+        Err(error) => {
+            error!(user_uuid = %user.uuid, %error, "Error checking chat ban");
+            Err(ApiError::from_code(ApiErrorCode::ServerError))
+        }
+    }
+}
+
+pub async fn authenticate(
+    state: &AppState,
+    credential: Credential,
+) -> Result<(User, u64), ApiError> {
+    let (uuid, exp) = match credential {
+        Credential::Token(token) => {
+            let claims = AuthRequestClaims::from_string(&token, &state.config.auth)
+                .map_err(|_| ApiError::from_code(ApiErrorCode::Unauthorized))?;
+            (claims.sub, claims.exp)
+        }
+        Credential::Ticket(ticket) => redeem_ticket(&state.pool, &ticket)
+            .await?
+            .ok_or(ApiError::from_code(ApiErrorCode::Unauthorized))?,
+    };
+    record_user(&uuid);
+    let user = get_active_user(state, &uuid).await?;
+    record_auth_outcome("success");
+
+    Ok((user, exp))
+}
+
+pub async fn reauthenticate(state: &AppState, uuid: &str, token: &str) -> Result<u64, ApiError> {
+    let claims = AuthRequestClaims::from_string(token, &state.config.auth)?;
+    if claims.sub != uuid {
+        return Err(ApiError::from_code(ApiErrorCode::InvalidToken));
+    }
+
+    get_active_user(state, &claims.sub).await?;
+    Ok(claims.exp)
+}
+
+fn server_error(error: sqlx::Error) -> String {
+    error!(%error, "Error handling chat message");
+    String::from("Server error")
+}
+
+fn validate_room(room: &str) -> Result<(), String> {
+    let is_valid = !room.is_empty()
+        && room.len() <= MAX_ROOM_LENGTH
+        && room.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_valid { Ok(()) } else { Err(format!("Invalid room name: {}", room)) }
+}
+
+fn validate_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() || text.len() > MAX_MESSAGE_LENGTH {
+        Err(String::from("Invalid message length"))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_emoji(emoji: &str) -> Result<(), String> {
+    let is_valid = !emoji.is_empty()
+        && emoji.len() <= MAX_EMOJI_LENGTH
+        && !emoji.is_ascii()
+        && !emoji.chars().any(|c| c.is_whitespace() || c.is_ascii_control());
+    if is_valid { Ok(()) } else { Err(format!("Invalid emoji: {}", emoji)) }
+}
+
+fn sanction_until(config: &WsConfig, duration: u64) -> Result<i64, String> {
+    i64::try_from(duration)
+        .ok()
+        .filter(|_| duration <= config.max_sanction_duration)
+        .and_then(|duration| current_timestamp().checked_add(duration))
+        .ok_or_else(|| String::from("Invalid duration"))
+}
+
+fn joined_room(connection: &Connection, room: Option<String>) -> Result<String, String> {
+    let room = room.unwrap_or_else(|| DEFAULT_ROOM.to_string());
+    if connection.rooms.contains(&room) { Ok(room) } else { Err(format!("Not in room: {}", room)) }
+}
+
+async fn check_room_access(pool: &DbPool, uuid: &str, room: &str) -> Result<(), ApiError> {
+    validate_room(room)
+        .map_err(|message| ApiError::with_detail(ApiErrorCode::InvalidMessage, message))?;
+    let is_banned = has_active_sanction(pool, uuid, Some(room), SanctionKind::Ban)
+        .await
+        .map_err(|error| ApiError::with_detail(ApiErrorCode::ServerError, server_error(error)))?;
+    if is_banned {
+        let message = format!("Banned from room: {}", room);
+        return Err(ApiError::with_detail(ApiErrorCode::AccessDenied, message));
+    }
+    Ok(())
+}
+
+pub async fn enter_room(connection: &mut Connection, room: &str) -> Result<bool, ApiError> {
+    if connection.rooms.contains(room) {
+        return Ok(false);
+    }
+
+    check_room_access(&connection.state.pool, &connection.user.uuid.to_string(), room).await?;
+    connection.rooms.insert(room.to_string());
+    Ok(true)
+}
+
+pub async fn mark_read(
+    state: &AppState,
+    uuid: String,
+    room: String,
+    message_id: i64,
+) -> Result<(), ApiError> {
+    check_room_access(&state.pool, &uuid, &room).await?;
+    match get_message_by_id(&state.pool, message_id).await {
+        Ok(message) if message.room == room => {}
+        Ok(_) | Err(sqlx::Error::RowNotFound) => {
+            return Err(ApiError::from_code(ApiErrorCode::MessageNotExist));
+        }
+        Err(error) => {
+            return Err(ApiError::with_detail(ApiErrorCode::ServerError, server_error(error)));
+        }
+    }
+
+    let result = upsert_read_marker(&state.pool, &uuid, &room, message_id)
+        .await
+        .map_err(|error| ApiError::with_detail(ApiErrorCode::ServerError, server_error(error)))?;
+    if result.rows_affected() > 0 {
+        state.pubsub.publish(WsServerMessage::Read { room, uuid, message_id });
+    }
+    Ok(())
+}
+
+pub async fn join_room(connection: &mut Connection, room: String) -> Result<(), String> {
+    if enter_room(connection, &room).await.map_err(|error| error.detail())? {
+        let username = connection.user.username.clone();
+        connection.state.pubsub.publish(WsServerMessage::Joined { room, username });
+    }
+    Ok(())
+}
+
+pub fn leave_rooms(connection: &mut Connection) {
+    let pubsub = connection.state.pubsub.clone();
+    for room in connection.rooms.drain() {
+        pubsub.publish(WsServerMessage::Left { room, username: connection.user.username.clone() });
+    }
+}
+
+async fn get_unsent_attachments(
+    connection: &Connection,
+    ids: Vec<String>,
+) -> Result<Vec<Attachment>, ApiError> {
+    if ids.len() > MAX_ATTACHMENTS {
+        let message = format!("Messages can have at most {} attachments", MAX_ATTACHMENTS);
+        return Err(ApiError::with_detail(ApiErrorCode::InvalidMessage, message));
+    }
+
+    let mut attachments: Vec<Attachment> = Vec::new();
+    for id in ids {
+        if attachments.iter().any(|attachment| attachment.id == id) {
+            continue;
+        }
+        match get_attachment_by_id(&connection.state.pool, &id).await {
+            Ok(attachment)
+                if attachment.uploader_uuid == connection.user.uuid.to_string()
+                    && attachment.message_id.is_none() =>
+            {
+                attachments.push(attachment);
+            }
+            Ok(_) | Err(sqlx::Error::RowNotFound) => {
+                let message = format!("Attachment not available: {}", id);
+                return Err(ApiError::with_detail(ApiErrorCode::InvalidMessage, message));
+            }
+            Err(error) => {
+                return Err(ApiError::with_detail(ApiErrorCode::ServerError, server_error(error)));
+            }
+        }
+    }
+    Ok(attachments)
+}
+
+async fn check_can_post(connection: &Connection, room: &str) -> Result<(), ApiError> {
+    let state = &connection.state;
+    if !state.rate_limits.check(&state.config.ws, &connection.user.uuid.to_string()) {
+        return Err(ApiError::from_code(ApiErrorCode::RateLimited));
+    }
+    let is_muted = has_active_sanction(
+        &connection.state.pool,
+        &connection.user.uuid.to_string(),
+        Some(room),
+        SanctionKind::Mute,
+    )
+    .await
+    .map_err(|error| ApiError::with_detail(ApiErrorCode::ServerError, server_error(error)))?;
+    if is_muted {
+        let message = String::from("You are muted");
+        return Err(ApiError::with_detail(ApiErrorCode::AccessDenied, message));
+    }
+    Ok(())
+}
+
+async fn get_reaction_target(
+    connection: &Connection,
+    id: i64,
+    emoji: &str,
+) -> Result<ChatMessage, String> {
+    validate_emoji(emoji)?;
+    let message = get_message_by_id(&connection.state.pool, id)
+        .await
+        .map_err(|_| String::from("Message does not exist"))?;
+    joined_room(connection, Some(message.room.clone()))?;
+    Ok(message)
+}
+
+pub async fn send_chat(
+    connection: &mut Connection,
+    room: Option<String>,
+    text: String,
+    attachments: Vec<String>,
+) -> Result<ChatMessage, ApiError> {
+    let room = joined_room(connection, room)
+        .map_err(|message| ApiError::with_detail(ApiErrorCode::AccessDenied, message))?;
+    if attachments.is_empty() || !text.is_empty() {
+        validate_text(&text)
+            .map_err(|message| ApiError::with_detail(ApiErrorCode::InvalidMessage, message))?;
+    }
+    let attachments = get_unsent_attachments(connection, attachments).await?;
+    check_can_post(connection, &room).await?;
+
+    let mut message = ChatMessage {
+        id: 0,
+        room,
+        user_uuid: connection.user.uuid.to_string(),
+        username: connection.user.username.clone(),
+        text,
+        created_at: current_timestamp(),
+        edited_at: None,
+        deleted_at: None,
+        attachments: Vec::new(),
+    };
+    let user_uuid = connection.user.uuid.to_string();
+    let (id, linked) = transaction(&connection.state.pool, |transaction| {
+        let message = message.clone();
+        let attachments = attachments.clone();
+        let user_uuid = user_uuid.clone();
+        Box::pin(async move {
+            let id = insert_message(&mut **transaction, &message).await?;
+            let mut linked = Vec::new();
+            for attachment in attachments {
+                let result =
+                    link_attachment(&mut **transaction, &attachment.id, &user_uuid, id).await?;
+                if result.rows_affected() > 0 {
+                    linked.push(attachment);
+                }
+            }
+            Ok((id, linked))
+        })
+    })
+    .await?;
+    message.id = id;
+    let config = &connection.state.config.attachments;
+    message.attachments =
+        linked.into_iter().map(|attachment| to_attachment_info(config, attachment)).collect();
+    connection.typing.remove(&message.room);
+    connection.state.pubsub.publish(WsServerMessage::Chat(message.clone()));
+    tokio::spawn(notify_mentions(connection.state.clone(), message.clone()));
+    Ok(message)
+}
+
+fn parse_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(username) = word.strip_prefix('@') else {
+            continue;
+        };
+        let username = username.trim_end_matches(|c: char| c.is_ascii_punctuation());
+        if !username.is_empty() && !mentions.iter().any(|mention| mention == username) {
+            mentions.push(username.to_string());
+        }
+        if mentions.len() == MAX_MENTIONS {
+            break;
+        }
+    }
+    mentions
+}
+
+async fn notify_mentions(state: AppState, message: ChatMessage) {
+    for username in parse_mentions(&message.text) {
+        let user = match state.users.get_by_username(&username).await {
+            Ok(user) if user.uuid.to_string() != message.user_uuid => user,
+            _ => continue,
+        };
+
+        let mut notification = NewNotification::new(
+            NotificationCategory::Mention,
+            "New mention",
+            format!(
+                "{} mentioned you in {}: {}",
+                message.username,
+                message.room,
+                message.text.chars().take(MENTION_PREVIEW_LENGTH).collect::<String>()
+            ),
+        );
+        notification.room = Some(message.room.clone());
+        notification.message_id = Some(message.id);
+        if let Err(error) = create_notification(&state, &user.uuid.to_string(), notification).await
+        {
+            error!(user_uuid = %user.uuid, %error, "Error creating mention notification");
+        }
+    }
+}
+
+async fn publish_reactions(state: &AppState, message: ChatMessage) -> Result<(), String> {
+    let reactions = get_reaction_counts(&state.pool, message.id).await.map_err(server_error)?;
+    state.pubsub.publish(WsServerMessage::Reactions {
+        id: message.id,
+        room: message.room,
+        reactions,
+    });
+    Ok(())
+}
+
+async fn get_moderation_target(connection: &Connection, uuid: &str) -> Result<String, String> {
+    if !connection.user.can_moderate() {
+        return Err(String::from("Access denied"));
+    }
+
+    let target = connection
+        .state
+        .users
+        .get_by_uuid(uuid)
+        .await
+        .map_err(|_| String::from("User does not exist"))?;
+    if target.is_admin && !connection.user.is_admin {
+        return Err(String::from("Cannot moderate an admin"));
+    }
+    Ok(target.uuid.to_string())
+}
+
+async fn record_moderation_action(
+    connection: &Connection,
+    action: ModerationActionType,
+    target_uuid: &str,
+    room: Option<&str>,
+    message_id: Option<i64>,
+    reason: Option<&str>,
+    expires_at: Option<i64>,
+) -> Result<(), String> {
+    let title = moderation_title(action);
+    let action = ModerationAction {
+        id: Uuid::new_v4().to_string(),
+        moderator_uuid: connection.user.uuid.to_string(),
+        target_uuid: Some(target_uuid.to_string()),
+        action: action.as_str().to_string(),
+        room: room.map(String::from),
+        message_id,
+        reason: reason.map(String::from),
+        expires_at,
+        created_at: current_timestamp(),
+    };
+    insert_moderation_action(&connection.state.pool, &action).await.map_err(server_error)?;
+    notify_moderation_action(&connection.state, title, &action);
+    Ok(())
+}
+
+fn moderation_title(action: ModerationActionType) -> &'static str {
+    match action {
+        ModerationActionType::Mute => "You were muted",
+        ModerationActionType::Unmute => "You were unmuted",
+        ModerationActionType::Kick => "You were kicked",
+        ModerationActionType::Ban => "You were banned",
+        ModerationActionType::Unban => "You were unbanned",
+        ModerationActionType::DeleteMessage => "Your message was deleted",
+    }
+}
+
+fn notify_moderation_action(state: &AppState, title: &str, action: &ModerationAction) {
+    let Some(target_uuid) = action.target_uuid.clone() else {
+        return;
+    };
+
+    let mut body = match &action.room {
+        Some(room) => format!("Room: {}", room),
+        None => String::from("Room: all rooms"),
+    };
+    if let Some(reason) = &action.reason {
+        body = format!("{}\nReason: {}", body, reason);
+    }
+
+    let mut notification = NewNotification::new(NotificationCategory::Moderation, title, body);
+    notification.room = action.room.clone();
+    notification.message_id = action.message_id;
+    notify(state, target_uuid, notification);
+}
+
+pub async fn handle_client_message(
+    connection: &mut Connection,
+    message: WsClientMessage,
+) -> Result<Option<WsServerMessage>, String> {
+    let pubsub = connection.state.pubsub.clone();
+    match message {
+        WsClientMessage::Auth { .. } => Ok(None),
+        WsClientMessage::Join { room } => {
+            join_room(connection, room).await?;
+            Ok(None)
+        }
+        WsClientMessage::Leave { room } => {
+            if connection.rooms.remove(&room) {
+                let username = connection.user.username.clone();
+                pubsub.publish(WsServerMessage::Left { room, username });
+            }
+            Ok(None)
+        }
+        WsClientMessage::Chat { room, text, attachments } => {
+            send_chat(connection, room, text, attachments).await.map_err(|error| error.detail())?;
+            Ok(None)
+        }
+        WsClientMessage::History { room, after } => {
+            let room = joined_room(connection, room)?;
+            let state = &connection.state;
+            let mut messages =
+                get_messages(state.read_pool(), &room, after, state.config.ws.history_size)
+                    .await
+                    .map_err(server_error)?;
+            load_attachments(&state.pool, &state.config.attachments, &mut messages)
+                .await
+                .map_err(server_error)?;
+            Ok(Some(WsServerMessage::History { room, messages }))
+        }
+        WsClientMessage::Typing { room } => {
+            let room = joined_room(connection, room)?;
+            let now = Instant::now();
+            if let Some(last) = connection.typing.get(&room) {
+                if now.duration_since(*last) < TYPING_THROTTLE {
+                    return Ok(None);
+                }
+            }
+
+            connection.typing.insert(room.clone(), now);
+            pubsub.publish(WsServerMessage::Typing {
+                room,
+                uuid: connection.user.uuid.to_string(),
+                username: connection.user.username.clone(),
+                expires_in: TYPING_EXPIRY,
+            });
+            Ok(None)
+        }
+        WsClientMessage::Read { room, message_id } => {
+            let room = joined_room(connection, room)?;
+            let uuid = connection.user.uuid.to_string();
+            mark_read(&connection.state, uuid, room, message_id)
+                .await
+                .map_err(|error| error.detail())?;
+            Ok(None)
+        }
+        WsClientMessage::React { id, emoji } => {
+            let message = get_reaction_target(connection, id, &emoji).await?;
+            let result = insert_reaction(
+                &connection.state.pool,
+                id,
+                &connection.user.uuid.to_string(),
+                &emoji,
+            )
+            .await
+            .map_err(server_error)?;
+            if result.rows_affected() > 0 {
+                publish_reactions(&connection.state, message).await?;
+            }
+            Ok(None)
+        }
+        WsClientMessage::Unreact { id, emoji } => {
+            let message = get_reaction_target(connection, id, &emoji).await?;
+            let result = delete_reaction(
+                &connection.state.pool,
+                id,
+                &connection.user.uuid.to_string(),
+                &emoji,
+            )
+            .await
+            .map_err(server_error)?;
+            if result.rows_affected() > 0 {
+                publish_reactions(&connection.state, message).await?;
+            }
+            Ok(None)
+        }
+        WsClientMessage::Edit { id, text } => {
+            validate_text(&text)?;
+            let message = get_message_by_id(&connection.state.pool, id)
+                .await
+                .map_err(|_| String::from("Message does not exist"))?;
+            if message.user_uuid != connection.user.uuid.to_string() {
+                return Err(String::from("Cannot edit another user's message"));
+            }
+            joined_room(connection, Some(message.room.clone()))?;
+            check_can_post(connection, &message.room).await.map_err(|error| error.detail())?;
+
+            let edited_at = current_timestamp();
+            update_message_text(&connection.state.pool, id, &text, edited_at)
+                .await
+                .map_err(server_error)?;
+            let room = message.room;
+            pubsub.publish(WsServerMessage::Edited { id, room, text, edited_at });
+            Ok(None)
+        }
+        WsClientMessage::Delete { id } => {
+            let message = get_message_by_id(&connection.state.pool, id)
+                .await
+                .map_err(|_| String::from("Message does not exist"))?;
+            if message.user_uuid != connection.user.uuid.to_string() {
+                if !connection.user.can_moderate() {
+                    return Err(String::from("Cannot delete another user's message"));
+                }
+                record_moderation_action(
+                    connection,
+                    ModerationActionType::DeleteMessage,
+                    &message.user_uuid,
+                    Some(&message.room),
+                    Some(id),
+                    None,
+                    None,
+                )
+                .await?;
+            }
+
+            delete_message(&connection.state.pool, id, current_timestamp())
+                .await
+                .map_err(server_error)?;
+            let room = message.room;
+            let deleted_by = connection.user.uuid.to_string();
+            pubsub.publish(WsServerMessage::Deleted { id, room, deleted_by });
+            Ok(None)
+        }
+        WsClientMessage::Mute { uuid, room, duration, reason } => {
+            let target_uuid = get_moderation_target(connection, &uuid).await?;
+            let until = sanction_until(&connection.state.config.ws, duration)?;
+            insert_sanction(
+                &connection.state.pool,
+                &target_uuid,
+                room.as_deref(),
+                SanctionKind::Mute,
+                Some(until),
+            )
+            .await
+            .map_err(server_error)?;
+            record_moderation_action(
+                connection,
+                ModerationActionType::Mute,
+                &target_uuid,
+                room.as_deref(),
+                None,
+                reason.as_deref(),
+                Some(until),
+            )
+            .await?;
+            pubsub.publish(WsServerMessage::Muted { uuid: target_uuid, room, until, reason });
+            Ok(None)
+        }
+        WsClientMessage::Unmute { uuid, room } => {
+            let target_uuid = get_moderation_target(connection, &uuid).await?;
+            delete_sanctions(
+                &connection.state.pool,
+                &target_uuid,
+                room.as_deref(),
+                SanctionKind::Mute,
+            )
+            .await
+            .map_err(server_error)?;
+            record_moderation_action(
+                connection,
+                ModerationActionType::Unmute,
+                &target_uuid,
+                room.as_deref(),
+                None,
+                None,
+                None,
+            )
+            .await?;
+            pubsub.publish(WsServerMessage::Unmuted { uuid: target_uuid, room });
+            Ok(None)
+        }
+        WsClientMessage::Kick { uuid, room, reason } => {
+            let target_uuid = get_moderation_target(connection, &uuid).await?;
+            record_moderation_action(
+                connection,
+                ModerationActionType::Kick,
+                &target_uuid,
+                room.as_deref(),
+                None,
+                reason.as_deref(),
+                None,
+            )
+            .await?;
+            pubsub.publish(WsServerMessage::Kicked { uuid: target_uuid, room, reason });
+            Ok(None)
+        }
+        WsClientMessage::Ban { uuid, room, duration, reason } => {
+            let target_uuid = get_moderation_target(connection, &uuid).await?;
+            let config = &connection.state.config.ws;
+            let until = duration.map(|duration| sanction_until(config, duration)).transpose()?;
+            insert_sanction(
+                &connection.state.pool,
+                &target_uuid,
+                room.as_deref(),
+                SanctionKind::Ban,
+                until,
+            )
+            .await
+            .map_err(server_error)?;
+            record_moderation_action(
+                connection,
+                ModerationActionType::Ban,
+                &target_uuid,
+                room.as_deref(),
+                None,
+                reason.as_deref(),
+                until,
+            )
+            .await?;
+            pubsub.publish(WsServerMessage::Banned { uuid: target_uuid, room, until, reason });
+            Ok(None)
+        }
+        WsClientMessage::Unban { uuid, room } => {
+            let target_uuid = get_moderation_target(connection, &uuid).await?;
+            delete_sanctions(
+                &connection.state.pool,
+                &target_uuid,
+                room.as_deref(),
+                SanctionKind::Ban,
+            )
+            .await
+            .map_err(server_error)?;
+            record_moderation_action(
+                connection,
+                ModerationActionType::Unban,
+                &target_uuid,
+                room.as_deref(),
+                None,
+                None,
+                None,
+            )
+            .await?;
+            pubsub.publish(WsServerMessage::Unbanned { uuid: target_uuid, room });
+            Ok(None)
+        }
+    }
+}
+
+pub fn apply_server_message(
+    connection: &mut Connection,
+    message: &WsServerMessage,
+) -> (bool, Option<(u16, &'static str)>) {
+    match message {
+        WsServerMessage::Notification(notification) => {
+            return (notification.user_uuid == connection.user.uuid.to_string(), None);
+        }
+        WsServerMessage::Disconnected { .. } => return (false, None),
+        _ => {}
+    }
+
+    let is_delivered = match message.room() {
+        Some(room) => connection.rooms.contains(room),
+        None => true,
+    };
+
+    let (uuid, room, code, reason) = match message {
+        WsServerMessage::Kicked { uuid, room, .. } => (uuid, room, CLOSE_KICKED, "Kicked"),
+        WsServerMessage::Banned { uuid, room, .. } => (uuid, room, CLOSE_BANNED, "Banned"),
+        _ => return (is_delivered, None),
+    };
+    if *uuid != connection.user.uuid.to_string() {
+        return (is_delivered, None);
+    }
+
+    match room {
+        Some(room) => {
+            connection.rooms.remove(room);
+            (is_delivered, None)
+        }
+        None => (true, Some((code, reason))),
+    }
+}
+
+pub async fn track_typing(pubsub: Arc<dyn PubSub>, typing: Arc<Typing>) {
+    let mut rx = pubsub.subscribe();
+    let mut prune_interval = interval(Duration::from_secs(TYPING_EXPIRY));
+    loop {
+        tokio::select! {
+            message = rx.recv() => match message {
+                Ok(WsServerMessage::Typing { room, uuid, username, expires_in }) => {
+                    typing.set(&room, &uuid, &username, expires_in);
+                }
+                Ok(WsServerMessage::Chat(message)) => {
+                    typing.clear(&message.room, &message.user_uuid);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            _ = prune_interval.tick() => typing.prune(),
+        }
+    }
+}
+
+pub async fn prune_rate_limits(rate_limits: Arc<RateLimits>, config: Arc<Config>) {
+    let window = Duration::from_secs(config.ws.rate_window);
+    let mut prune_interval = interval(window);
+    loop {
+        prune_interval.tick().await;
+        rate_limits.prune(window);
+    }
+}
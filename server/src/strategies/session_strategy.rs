@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dash_types::ws::WsServerMessage;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::pubsub::PubSub;
+
+type SessionMap = HashMap<String, HashMap<u64, mpsc::UnboundedSender<SessionCommand>>>;
+
+#[derive(Clone, Debug)]
+pub enum SessionCommand {
+    Disconnect { code: u16, reason: String },
+}
+
+#[derive(Default)]
+pub struct Sessions {
+    sessions: Mutex<SessionMap>,
+    next_id: AtomicU64,
+}
+
+impl Sessions {
+    pub fn register(self: &Arc<Self>, uuid: String) -> Session {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, commands) = mpsc::unbounded_channel();
+        self.sessions.lock().unwrap().entry(uuid.clone()).or_default().insert(id, tx);
+        Session { id, uuid, commands, sessions: self.clone() }
+    }
+
+    fn disconnect(&self, uuid: &str, code: u16, reason: &str) -> usize {
+        let sessions = self.sessions.lock().unwrap();
+        let Some(user_sessions) = sessions.get(uuid) else {
+            return 0;
+        };
+
+        let command = SessionCommand::Disconnect { code, reason: reason.to_string() };
+        user_sessions.values().filter(|tx| tx.send(command.clone()).is_ok()).count()
+    }
+
+    pub fn disconnect_all(&self, code: u16, reason: &str) -> usize {
+        let sessions = self.sessions.lock().unwrap();
+        let command = SessionCommand::Disconnect { code, reason: reason.to_string() };
+        sessions
+            .values()
+            .flat_map(HashMap::values)
+            .filter(|tx| tx.send(command.clone()).is_ok())
+            .count()
+    }
+
+    pub fn count(&self) -> usize {
+        self.sessions.lock().unwrap().values().map(HashMap::len).sum()
+    }
+}
+
+pub struct Session {
+    pub id: u64,
+    pub uuid: String,
+    pub commands: mpsc::UnboundedReceiver<SessionCommand>,
+    sessions: Arc<Sessions>,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let mut sessions = self.sessions.sessions.lock().unwrap();
+        if let Some(user_sessions) = sessions.get_mut(&self.uuid) {
+            user_sessions.remove(&self.id);
+            if user_sessions.is_empty() {
+                sessions.remove(&self.uuid);
+            }
+        }
+    }
+}
+
+pub fn disconnect_user(pubsub: &dyn PubSub, uuid: &str, code: u16, reason: &str) {
+    pubsub.publish(WsServerMessage::Disconnected {
+        uuid: uuid.to_string(),
+        code,
+        reason: reason.to_string(),
+    });
+}
+
+pub async fn track_sessions(pubsub: Arc<dyn PubSub>, sessions: Arc<Sessions>) {
+    let mut rx = pubsub.subscribe();
+    loop {
+        match rx.recv().await {
+            Ok(WsServerMessage::Disconnected { uuid, code, reason }) => {
+                sessions.disconnect(&uuid, code, &reason);
+            }
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pubsub::InProcessPubSub;
+
+    #[tokio::test]
+    async fn disconnect_reaches_sessions_through_pubsub() {
+        let pubsub: Arc<dyn PubSub> = Arc::new(InProcessPubSub::new(16));
+        let sessions = Arc::new(Sessions::default());
+        let tracker = tokio::spawn(track_sessions(pubsub.clone(), sessions.clone()));
+        tokio::task::yield_now().await;
+
+        let mut session = sessions.register(String::from("disconnect-test"));
+        disconnect_user(pubsub.as_ref(), "disconnect-test", 4000, "Removed");
+        let command = session.commands.recv().await.unwrap();
+        assert!(matches!(command, SessionCommand::Disconnect { code: 4000, .. }));
+        tracker.abort();
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use dash_types::chat::TypingUser;
+
+type TypingMap = HashMap<String, HashMap<String, (String, Instant)>>;
+
+#[derive(Default)]
+pub struct Typing {
+    rooms: Mutex<TypingMap>,
+}
+
+impl Typing {
+    pub fn set(&self, room: &str, uuid: &str, username: &str, expires_in: u64) {
+        let expiry = Instant::now() + Duration::from_secs(expires_in);
+        self.rooms
+            .lock()
+            .unwrap()
+            .entry(room.to_string())
+            .or_default()
+            .insert(uuid.to_string(), (username.to_string(), expiry));
+    }
+
+    pub fn clear(&self, room: &str, uuid: &str) {
+        let mut rooms = self.rooms.lock().unwrap();
+        if let Some(room_typing) = rooms.get_mut(room) {
+            room_typing.remove(uuid);
+            if room_typing.is_empty() {
+                rooms.remove(room);
+            }
+        }
+    }
+
+    pub fn get(&self, room: &str) -> Vec<TypingUser> {
+        let now = Instant::now();
+        let rooms = self.rooms.lock().unwrap();
+        let Some(room_typing) = rooms.get(room) else {
+            return Vec::new();
+        };
+
+        room_typing
+            .iter()
+            .filter(|(_, (_, expiry))| *expiry > now)
+            .map(|(uuid, (username, _))| TypingUser {
+                uuid: uuid.clone(),
+                username: username.clone(),
+            })
+            .collect()
+    }
+
+    pub fn prune(&self) {
+        let now = Instant::now();
+        let mut rooms = self.rooms.lock().unwrap();
+        for room_typing in rooms.values_mut() {
+            room_typing.retain(|_, (_, expiry)| *expiry > now);
+        }
+        rooms.retain(|_, room_typing| !room_typing.is_empty());
+    }
+}
@@ -1,58 +1,170 @@
-use std::convert::TryInto;
-use std::env;
-
-use bcrypt::{DEFAULT_COST, hash_with_salt};
-use dash_types::user::{RegisterUser, User, UserInfo};
-use once_cell::sync::Lazy;
-use sqlx::any::AnyQueryResult;
+use bcrypt::hash_with_salt;
+use dash_types::user::{User, UserInfo};
+use sqlx::{Any, Executor};
 use uuid::Uuid;
 
-use crate::pool;
+use crate::dialect::Dialect;
+use crate::error::DbError;
+use crate::repositories::user_repository::NewUser;
+
+#[cfg(not(test))]
+const PASSWORD_COST: u32 = bcrypt::DEFAULT_COST;
 
-static PASSWORD_SALT: Lazy<[u8; 16]> = Lazy::new(|| {
-    env::var("PASSWORD_SALT")
-        .expect("Missing PASSWORD_SALT environment variable")
-        .as_bytes()
-        .try_into()
-        .expect("PASSWORD_SALT is not 16 characters long")
-});
+#[cfg(test)]
+const PASSWORD_COST: u32 = 4;
+
+pub fn hash_password(password: String, salt: [u8; 16]) -> String {
+    hash_with_salt(password, PASSWORD_COST, salt).unwrap().to_string()
+}
 
-pub async fn get_all_users() -> Result<Vec<UserInfo>, sqlx::Error> {
-    let query = "SELECT * FROM \"users\";";
-    sqlx::query_as::<_, UserInfo>(query).fetch_all(&pool::get_pool()).await
+fn user_columns(dialect: Dialect) -> String {
+    format!(
+        "id, {}, username, email, password, {}, {}, {}, {}, {}",
+        dialect.select_uuid("uuid"),
+        dialect.select_bool("is_admin"),
+        dialect.select_bool("is_moderator"),
+        dialect.select_bool("is_disabled"),
+        dialect.select_timestamp("created_at"),
+        dialect.select_timestamp("updated_at")
+    )
 }
 
-pub async fn get_db_user_by_username_or_email(
-    username_or_email: String,
-) -> Result<User, sqlx::Error> {
-    let query = "SELECT * FROM \"users\" WHERE username = $1 OR email = $1;";
-    sqlx::query_as::<_, User>(query).bind(username_or_email).fetch_one(&pool::get_pool()).await
+async fn get_user<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    condition: &str,
+    value: &str,
+) -> Result<User, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = format!("SELECT {} FROM \"users\" WHERE {};", user_columns(dialect), condition);
+    Ok(sqlx::query_as::<_, User>(&query).bind(value).fetch_one(executor).await?)
 }
 
-pub async fn get_db_user_by_uuid(uuid: String) -> Result<User, sqlx::Error> {
-    let query = "SELECT * FROM \"users\" WHERE uuid = $1;";
-    sqlx::query_as::<_, User>(query).bind(uuid).fetch_one(&pool::get_pool()).await
+pub async fn get_all_users<'e, E>(executor: E, dialect: Dialect) -> Result<Vec<UserInfo>, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = format!("SELECT {} FROM \"users\";", user_columns(dialect));
+    Ok(sqlx::query_as::<_, UserInfo>(&query).fetch_all(executor).await?)
 }
 
-pub async fn delete_user_by_uuid(uuid: String) -> Result<AnyQueryResult, sqlx::Error> {
-    let query = "DELETE FROM \"users\" WHERE uuid = $1;";
-    sqlx::query(query).bind(uuid).execute(&pool::get_pool()).await
+pub async fn get_user_by_uuid<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    uuid: &str,
+) -> Result<User, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let uuid = Uuid::parse_str(uuid).map_err(|_| DbError::NotFound)?;
+    let condition = format!("uuid = {}", dialect.bind_uuid(1));
+    get_user(executor, dialect, &condition, &uuid.to_string()).await
 }
 
-pub async fn insert_db_user(register_user: RegisterUser) -> Result<User, sqlx::Error> {
-    let uuid = Uuid::new_v4();
-    let mut salt: [u8; 16] = [0; 16];
-    salt.copy_from_slice(&PASSWORD_SALT.clone());
+pub async fn get_user_by_username<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    username: &str,
+) -> Result<User, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    get_user(executor, dialect, "LOWER(username) = LOWER($1)", username).await
+}
+
+pub async fn get_user_by_username_or_email<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    username_or_email: &str,
+) -> Result<User, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let condition = "LOWER(username) = LOWER($1) OR LOWER(email) = LOWER($1)";
+    get_user(executor, dialect, condition, username_or_email).await
+}
+
+pub async fn insert_user<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    user: &NewUser,
+) -> Result<User, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let query = format!(
+        "INSERT INTO \"users\" (uuid, username, email, password, is_admin)
+        VALUES ({}, $2, $3, $4, $5)
+        RETURNING {};",
+        dialect.bind_uuid(1),
+        user_columns(dialect)
+    );
+    let user = sqlx::query_as::<_, User>(&query)
+        .bind(user.uuid.to_string())
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.password)
+        .bind(user.is_admin)
+        .fetch_one(executor)
+        .await?;
+    Ok(user)
+}
+
+pub async fn delete_user<'e, E>(executor: E, dialect: Dialect, uuid: &str) -> Result<u64, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let Ok(uuid) = Uuid::parse_str(uuid) else {
+        return Ok(0);
+    };
+    let query = format!("DELETE FROM \"users\" WHERE uuid = {};", dialect.bind_uuid(1));
+    let result = sqlx::query(&query).bind(uuid.to_string()).execute(executor).await?;
+    Ok(result.rows_affected())
+}
+
+pub async fn set_user_flag<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    column: &str,
+    uuid: &str,
+    value: bool,
+) -> Result<u64, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let Ok(uuid) = Uuid::parse_str(uuid) else {
+        return Ok(0);
+    };
+    let query = format!(
+        "UPDATE \"users\" SET {} = $1, updated_at = {} WHERE uuid = {};",
+        column,
+        dialect.current_timestamp(),
+        dialect.bind_uuid(2)
+    );
+    let result = sqlx::query(&query).bind(value).bind(uuid.to_string()).execute(executor).await?;
+    Ok(result.rows_affected())
+}
 
-    let query = "INSERT INTO \"users\" (uuid, username, email, password, is_admin)
-        VALUES ($1, $2, $3, $4, $5)
-        RETURNING *;";
-    sqlx::query_as::<_, User>(query)
-        .bind(uuid.to_string())
-        .bind(register_user.username)
-        .bind(register_user.email.to_string())
-        .bind(hash_with_salt(register_user.password, DEFAULT_COST, salt).unwrap().to_string())
-        .bind(false)
-        .fetch_one(&pool::get_pool())
-        .await
+pub async fn set_user_password<'e, E>(
+    executor: E,
+    dialect: Dialect,
+    uuid: &str,
+    password: &str,
+) -> Result<u64, DbError>
+where
+    E: Executor<'e, Database = Any>,
+{
+    let Ok(uuid) = Uuid::parse_str(uuid) else {
+        return Ok(0);
+    };
+    let query = format!(
+        "UPDATE \"users\" SET password = $1, updated_at = {} WHERE uuid = {};",
+        dialect.current_timestamp(),
+        dialect.bind_uuid(2)
+    );
+    let result =
+        sqlx::query(&query).bind(password).bind(uuid.to_string()).execute(executor).await?;
+    Ok(result.rows_affected())
 }
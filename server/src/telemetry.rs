@@ -0,0 +1,382 @@
+use std::fmt;
+
+use axum::extract::Request;
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Span, Subscriber};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, fmt as subscriber_fmt};
+
+use crate::config::LogConfig;
+use crate::metrics::{QUERY_TARGET, QueryMetrics};
+
+const REDACTED: &str = "[redacted]";
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const SENSITIVE_FIELDS: [&str; 8] =
+    ["authorization", "claims", "cookie", "password", "salt", "secret", "ticket", "token"];
+
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider {
+            if let Err(error) = provider.shutdown() {
+                tracing::warn!(%error, "Error flushing OpenTelemetry spans");
+            }
+        }
+    }
+}
+
+struct FieldVisitor<'a> {
+    fields: Map<String, Value>,
+    redact: &'a [String],
+}
+
+impl<'a> FieldVisitor<'a> {
+    fn new(redact: &'a [String]) -> Self {
+        Self { fields: Map::new(), redact }
+    }
+
+    fn insert(&mut self, field: &Field, value: Value) {
+        let name = field.name();
+        let lowercase = name.to_lowercase();
+        let is_sensitive = SENSITIVE_FIELDS.iter().any(|sensitive| lowercase.contains(sensitive))
+            || self.redact.iter().any(|sensitive| lowercase.contains(sensitive.as_str()));
+        let value = if is_sensitive { Value::from(REDACTED) } else { value };
+        self.fields.insert(name.to_string(), value);
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_error(&mut self, field: &Field, value: &(dyn std::error::Error + 'static)) {
+        self.insert(field, Value::from(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::from(format!("{:?}", value)));
+    }
+}
+
+fn write_text(writer: &mut Writer<'_>, mut fields: Map<String, Value>) -> fmt::Result {
+    let mut separator = "";
+    if let Some(message) = fields.remove("message") {
+        write!(writer, "{}", message.as_str().unwrap_or_default())?;
+        separator = " ";
+    }
+    for (name, value) in fields {
+        match value {
+            Value::String(value) => write!(writer, "{}{}={}", separator, name, value)?,
+            value => write!(writer, "{}{}={}", separator, name, value)?,
+        }
+        separator = " ";
+    }
+    Ok(())
+}
+
+struct RedactedFields {
+    json: bool,
+    redact: Vec<String>,
+}
+
+impl<'writer> FormatFields<'writer> for RedactedFields {
+    fn format_fields<R: tracing_subscriber::field::RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = FieldVisitor::new(&self.redact);
+        fields.record(&mut visitor);
+        if self.json {
+            write!(writer, "{}", Value::Object(visitor.fields))
+        } else {
+            write_text(&mut writer, visitor.fields)
+        }
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        if !self.json {
+            if !current.fields.is_empty() {
+                current.fields.push(' ');
+            }
+            return self.format_fields(current.as_writer(), fields);
+        }
+
+        let mut visitor = FieldVisitor::new(&self.redact);
+        if let Ok(Value::Object(existing)) = serde_json::from_str(&current.fields) {
+            visitor.fields = existing;
+        }
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.fields).to_string();
+        Ok(())
+    }
+}
+
+struct JsonEvents {
+    redact: Vec<String>,
+}
+
+impl<S, N> FormatEvent<S, N> for JsonEvents
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'writer> FormatFields<'writer> + 'static,
+{
+    fn format_event(
+        &self,
+        context: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+        let metadata = event.metadata();
+
+        let mut object = Map::new();
+        object.insert(String::from("timestamp"), Value::from(timestamp));
+        object.insert(String::from("level"), Value::from(metadata.level().as_str()));
+        object.insert(String::from("target"), Value::from(metadata.target()));
+
+        let mut spans = Vec::new();
+        for span in context.event_scope().into_iter().flat_map(|scope| scope.from_root()) {
+            let mut span_object = Map::new();
+            span_object.insert(String::from("name"), Value::from(span.name()));
+            if let Some(fields) = span.extensions().get::<FormattedFields<N>>() {
+                if let Ok(Value::Object(fields)) = serde_json::from_str(&fields.fields) {
+                    span_object.extend(fields);
+                }
+            }
+            spans.push(Value::Object(span_object));
+        }
+        if !spans.is_empty() {
+            object.insert(String::from("spans"), Value::Array(spans));
+        }
+
+        let mut visitor = FieldVisitor::new(&self.redact);
+        event.record(&mut visitor);
+        object.extend(visitor.fields);
+        writeln!(writer, "{}", Value::Object(object))
+    }
+}
+
+#[cfg(feature = "otlp")]
+fn create_tracer_provider(
+    log: &LogConfig,
+    endpoint: &str,
+) -> Result<opentelemetry_sdk::trace::SdkTracerProvider, String> {
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|error| format!("Could not create OTLP exporter: {}", error))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(log.service_name.clone()).build())
+        .build();
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    opentelemetry::global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+fn format_layer<S, W>(log: &LogConfig, writer: W) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let fields = RedactedFields { json: log.format == "json", redact: log.redact.clone() };
+    let layer = subscriber_fmt::layer().with_writer(writer);
+    if fields.json {
+        let events = JsonEvents { redact: log.redact.clone() };
+        layer.event_format(events).fmt_fields(fields).boxed()
+    } else {
+        layer.fmt_fields(fields).boxed()
+    }
+}
+
+fn env_filter(log: &LogConfig) -> Result<EnvFilter, String> {
+    EnvFilter::builder().parse(&log.level).map_err(|error| error.to_string())
+}
+
+pub fn init(log: &LogConfig) -> Result<Telemetry, String> {
+    let queries = Targets::new().with_target(QUERY_TARGET, Level::TRACE);
+    let registry = tracing_subscriber::registry()
+        .with(format_layer(log, std::io::stdout).with_filter(env_filter(log)?))
+        .with(QueryMetrics.with_filter(queries));
+
+    #[cfg(feature = "otlp")]
+    {
+        use opentelemetry::trace::TracerProvider;
+
+        let provider = match &log.otlp_endpoint {
+            Some(endpoint) => Some(create_tracer_provider(log, endpoint)?),
+            None => None,
+        };
+        let layer = match &provider {
+            Some(provider) => Some(
+                tracing_opentelemetry::layer()
+                    .with_tracer(provider.tracer("dash_server"))
+                    .with_filter(env_filter(log)?),
+            ),
+            None => None,
+        };
+        registry.with(layer).try_init().map_err(|error| error.to_string())?;
+        Ok(Telemetry { provider })
+    }
+
+    #[cfg(not(feature = "otlp"))]
+    {
+        registry.try_init().map_err(|error| error.to_string())?;
+        Ok(Telemetry {})
+    }
+}
+
+#[cfg(feature = "otlp")]
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+#[cfg(feature = "otlp")]
+impl opentelemetry::propagation::Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        path = request.uri().path(),
+        request_id,
+        user_uuid = tracing::field::Empty,
+    );
+
+    #[cfg(feature = "otlp")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(request.headers()))
+        });
+        let _ = span.set_parent(parent);
+    }
+
+    span
+}
+
+pub fn record_user(uuid: &str) {
+    Span::current().record("user_uuid", uuid);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use serde_json::Value;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::{format_layer, record_user};
+    use crate::config::LogConfig;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_output_redacts_fields_and_carries_request_span() {
+        let log = LogConfig {
+            format: String::from("json"),
+            level: String::from("info"),
+            redact: vec![String::from("email")],
+            #[cfg(feature = "otlp")]
+            otlp_endpoint: None,
+            #[cfg(feature = "otlp")]
+            service_name: String::from("dash_server"),
+        };
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber =
+            tracing_subscriber::registry().with(format_layer(&log, move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "request",
+                request_id = "abc",
+                user_uuid = tracing::field::Empty
+            );
+            let _entered = span.enter();
+            record_user("1234");
+            tracing::info!(
+                password = "hunter2",
+                jwt_token = "t",
+                user_email = "a@b.c",
+                "Signed in"
+            );
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["message"], "Signed in");
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["password"], "[redacted]");
+        assert_eq!(line["jwt_token"], "[redacted]");
+        assert_eq!(line["user_email"], "[redacted]");
+        assert_eq!(line["spans"][0]["request_id"], "abc");
+        assert_eq!(line["spans"][0]["user_uuid"], "1234");
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use dash_types::user::User;
+use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use http::{HeaderMap, Method, Request, StatusCode};
+use serde_json::Value;
+use sqlx::any::AnyPoolOptions;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use crate::config::{Config, ConfigArgs};
+use crate::dialect::Dialect;
+use crate::migrate::get_migrator;
+use crate::pool::{DatabaseConfig, DbPool, create_pool};
+use crate::pubsub::InProcessPubSub;
+use crate::repositories::user_repository::memory::InMemoryUserRepository;
+use crate::repositories::user_repository::{NewUser, UserRepository};
+use crate::state::AppState;
+use crate::storage::LocalStorage;
+use crate::strategies::auth_strategy::{AuthClaims, JWTClaims};
+use crate::strategies::user_strategy::hash_password;
+
+pub const PASSWORD: &str = "password";
+
+pub fn test_flags() -> HashMap<String, String> {
+    [
+        ("AUTH_TOKEN_EXPIRY", "3600"),
+        ("DATABASE_URL", "sqlite::memory:"),
+        ("JWT_AUDIENCE", "test"),
+        ("JWT_ISSUER", "test"),
+        ("JWT_SECRET", "test"),
+        ("PASSWORD_SALT", "0123456789abcdef"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
+pub fn test_config() -> Config {
+    Config::load(&ConfigArgs { flags: test_flags(), ..ConfigArgs::default() }).unwrap()
+}
+
+pub fn test_database(url: &str) -> DatabaseConfig {
+    DatabaseConfig {
+        url: url.to_string(),
+        dialect: Dialect::from_url(url).unwrap(),
+        migrations: String::from("run"),
+        connect_attempts: 1,
+        connect_backoff: Duration::ZERO,
+        sqlite_journal_mode: String::from("WAL"),
+        sqlite_busy_timeout: Duration::from_secs(5),
+        max_connections: 100,
+        min_connections: 0,
+        acquire_timeout: Duration::from_secs(30),
+        idle_timeout: Duration::from_secs(1),
+        max_lifetime: Duration::from_secs(1800),
+        statement_timeout: Duration::ZERO,
+        replica_url: None,
+    }
+}
+
+pub fn test_state(users: Arc<InMemoryUserRepository>) -> AppState {
+    sqlx::any::install_default_drivers();
+    let config = test_config();
+    let pool = AnyPoolOptions::new().max_connections(1).connect_lazy(&config.database.url).unwrap();
+    AppState {
+        config: Arc::new(config),
+        pool,
+        replica: None,
+        users,
+        pubsub: Arc::new(InProcessPubSub::new(16)),
+        storage: Arc::new(LocalStorage::new(env::temp_dir().join("dash-test-attachments"))),
+        ws: Arc::default(),
+        sessions: Arc::default(),
+        typing: Arc::default(),
+        rate_limits: Arc::default(),
+    }
+}
+
+pub async fn test_pool(url: &str) -> DbPool {
+    let database = test_database(url);
+    let pool = create_pool(&database).await.unwrap();
+    get_migrator(database.dialect).run(&pool).await.unwrap();
+    pool
+}
+
+pub async fn create_user(users: &InMemoryUserRepository, username: &str) -> User {
+    let new_user = NewUser {
+        uuid: Uuid::new_v4(),
+        username: username.to_string(),
+        email: format!("{}@example.com", username),
+        password: hash_password(PASSWORD.to_string(), test_config().auth.password_salt),
+        is_admin: false,
+    };
+    users.insert(new_user).await.unwrap()
+}
+
+pub fn token_for(user: &User) -> String {
+    let auth = test_config().auth;
+    AuthClaims::from_user(user, &auth).unwrap().generate_token(&auth).unwrap().to_string()
+}
+
+pub async fn send(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    token: Option<&str>,
+    body: impl Into<Body>,
+) -> (StatusCode, HeaderMap, Value) {
+    let mut request =
+        Request::builder().method(method).uri(uri).header(CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+
+    let response = router.clone().oneshot(request.body(body.into()).unwrap()).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    (status, headers, body)
+}
@@ -0,0 +1,159 @@
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use std::{fs, io};
+
+use axum::serve::Listener;
+use rustls::crypto::ring::{default_provider, sign};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::server::TlsStream;
+use tracing::{debug, info, warn};
+
+use crate::config::TlsConfig;
+
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const TLS_ACCEPT_QUEUE: usize = 64;
+const TLS_ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+struct CertificateResolver {
+    cert: PathBuf,
+    key: PathBuf,
+    certified_key: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertificateResolver {
+    fn load(cert: &Path, key: &Path) -> Result<Self, String> {
+        Ok(Self {
+            cert: cert.to_path_buf(),
+            key: key.to_path_buf(),
+            certified_key: RwLock::new(Arc::new(load_certified_key(cert, key)?)),
+        })
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let cert = fs::metadata(&self.cert).and_then(|metadata| metadata.modified()).ok()?;
+        let key = fs::metadata(&self.key).and_then(|metadata| metadata.modified()).ok()?;
+        Some((cert, key))
+    }
+
+    fn reload(&self) -> Result<(), String> {
+        let certified_key = load_certified_key(&self.cert, &self.key)?;
+        *self.certified_key.write().unwrap() = Arc::new(certified_key);
+        Ok(())
+    }
+}
+
+impl ResolvesServerCert for CertificateResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.certified_key.read().unwrap().clone())
+    }
+}
+
+fn load_certified_key(cert: &Path, key: &Path) -> Result<CertifiedKey, String> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|error| format!("Could not read TLS certificate {}: {}", cert.display(), error))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", cert.display()));
+    }
+    let private_key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|error| format!("Could not read TLS key {}: {}", key.display(), error))?;
+    let signing_key = sign::any_supported_type(&private_key)
+        .map_err(|error| format!("Unsupported TLS key {}: {}", key.display(), error))?;
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+fn watch_certificate(resolver: Arc<CertificateResolver>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut modified = resolver.modified();
+        loop {
+            sleep(interval).await;
+            let current = resolver.modified();
+            if current.is_none() || current == modified {
+                continue;
+            }
+            modified = current;
+            match resolver.reload() {
+                Ok(()) => info!("Reloaded TLS certificate from {}", resolver.cert.display()),
+                Err(error) => warn!("Keeping previous TLS certificate: {}", error),
+            }
+        }
+    });
+}
+
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, tls: &TlsConfig) -> Result<Self, String> {
+        let resolver = Arc::new(CertificateResolver::load(&tls.cert, &tls.key)?);
+        let mut server_config =
+            rustls::ServerConfig::builder_with_provider(Arc::new(default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(|error| error.to_string())?
+                .with_no_client_auth()
+                .with_cert_resolver(resolver.clone());
+        server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        watch_certificate(resolver, tls.reload_interval);
+
+        let local_addr = listener.local_addr().map_err(|error| error.to_string())?;
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let (tx, incoming) = mpsc::channel(TLS_ACCEPT_QUEUE);
+        tokio::spawn(async move {
+            loop {
+                let (stream, addr) = tokio::select! {
+                    result = listener.accept() => match result {
+                        Ok(accepted) => accepted,
+                        Err(error) => {
+                            warn!(%error, "Could not accept connection");
+                            sleep(TLS_ACCEPT_BACKOFF).await;
+                            continue;
+                        }
+                    },
+                    _ = tx.closed() => break,
+                };
+
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(stream)) => {
+                            let _ = tx.send((stream, addr)).await;
+                        }
+                        Ok(Err(error)) => debug!(%addr, %error, "TLS handshake failed"),
+                        Err(_) => debug!(%addr, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+
+        Ok(Self { incoming, local_addr })
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(accepted) => accepted,
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
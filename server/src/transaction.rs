@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use sqlx::{Any, Transaction};
+use tokio::time::sleep;
+use tracing::{error, warn};
+
+use crate::error::DbError;
+use crate::pool::DbPool;
+
+pub type DbTransaction = Transaction<'static, Any>;
+
+const TRANSACTION_ATTEMPTS: u32 = 3;
+const TRANSACTION_BACKOFF: Duration = Duration::from_millis(25);
+
+async fn run_once<T, F>(pool: &DbPool, operation: &mut F) -> Result<T, DbError>
+where
+    F: for<'t> FnMut(&'t mut DbTransaction) -> BoxFuture<'t, Result<T, DbError>>,
+{
+    let mut transaction = pool.begin().await?;
+    match operation(&mut transaction).await {
+        Ok(value) => {
+            transaction.commit().await?;
+            Ok(value)
+        }
+        Err(error) => {
+            if let Err(rollback_error) = transaction.rollback().await {
+                error!(error = %rollback_error, "Error rolling back transaction");
+            }
+            Err(error)
+        }
+    }
+}
+
+pub async fn transaction<T, F>(pool: &DbPool, mut operation: F) -> Result<T, DbError>
+where
+    F: for<'t> FnMut(&'t mut DbTransaction) -> BoxFuture<'t, Result<T, DbError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match run_once(pool, &mut operation).await {
+            Err(error) if attempt < TRANSACTION_ATTEMPTS && error.is_serialization_failure() => {
+                warn!(
+                    "Transaction failed to serialize (attempt {} of {}), retrying: {}",
+                    attempt, TRANSACTION_ATTEMPTS, error
+                );
+                sleep(TRANSACTION_BACKOFF * attempt).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::error::Error;
+    use std::{env, fmt};
+
+    use dash_types::chat::ChatMessage;
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    use super::*;
+    use crate::strategies::chat_strategy::{
+        current_timestamp, get_message_by_id, get_messages, insert_message,
+    };
+    use crate::test_utils::test_pool;
+
+    #[derive(Debug)]
+    struct SerializationFailure;
+
+    impl fmt::Display for SerializationFailure {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", self.message())
+        }
+    }
+
+    impl Error for SerializationFailure {}
+
+    impl DatabaseError for SerializationFailure {
+        fn message(&self) -> &str {
+            "could not serialize access due to concurrent update"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed("40001"))
+        }
+
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    fn new_message() -> ChatMessage {
+        ChatMessage {
+            id: 0,
+            room: uuid::Uuid::new_v4().to_string(),
+            user_uuid: uuid::Uuid::new_v4().to_string(),
+            username: String::from("alice"),
+            text: String::from("hello"),
+            created_at: current_timestamp(),
+            edited_at: None,
+            deleted_at: None,
+            attachments: Vec::new(),
+        }
+    }
+
+    async fn assert_commits(pool: &DbPool) {
+        let message = new_message();
+        let result = transaction(pool, |transaction| {
+            let message = message.clone();
+            Box::pin(async move { Ok(insert_message(&mut **transaction, &message).await?) })
+        })
+        .await;
+
+        assert!(get_message_by_id(pool, result.unwrap()).await.is_ok());
+    }
+
+    async fn assert_rolls_back(pool: &DbPool) {
+        let message = new_message();
+        let mut attempts = 0;
+        let result: Result<(), DbError> = transaction(pool, |transaction| {
+            attempts += 1;
+            let message = message.clone();
+            Box::pin(async move {
+                insert_message(&mut **transaction, &message).await?;
+                Err(DbError::NotFound)
+            })
+        })
+        .await;
+
+        assert!(matches!(result, Err(DbError::NotFound)));
+        assert_eq!(attempts, 1);
+        assert!(get_messages(pool, &message.room, None, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn commits_on_success_sqlite() {
+        assert_commits(&test_pool("sqlite::memory:").await).await;
+    }
+
+    #[tokio::test]
+    async fn rolls_back_on_error_sqlite() {
+        assert_rolls_back(&test_pool("sqlite::memory:").await).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a PostgreSQL database in TEST_POSTGRES_URL"]
+    async fn commits_on_success_postgres() {
+        let url = env::var("TEST_POSTGRES_URL").expect("TEST_POSTGRES_URL is not set");
+        assert_commits(&test_pool(&url).await).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a PostgreSQL database in TEST_POSTGRES_URL"]
+    async fn rolls_back_on_error_postgres() {
+        let url = env::var("TEST_POSTGRES_URL").expect("TEST_POSTGRES_URL is not set");
+        assert_rolls_back(&test_pool(&url).await).await;
+    }
+
+    #[tokio::test]
+    async fn retries_serialization_failure() {
+        let pool = test_pool("sqlite::memory:").await;
+        let message = new_message();
+        let mut attempts = 0;
+        let result = transaction(&pool, |transaction| {
+            attempts += 1;
+            let attempt = attempts;
+            let message = message.clone();
+            Box::pin(async move {
+                let id = insert_message(&mut **transaction, &message).await?;
+                if attempt == 1 {
+                    let error = sqlx::Error::Database(Box::new(SerializationFailure));
+                    return Err(DbError::from(error));
+                }
+                Ok(id)
+            })
+        })
+        .await;
+
+        assert_eq!(attempts, 2);
+        let messages = get_messages(&pool, &message.room, None, 10).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, result.unwrap());
+    }
+}
@@ -0,0 +1,165 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use dash_types::notification::NotificationCategory;
+use dash_types::ws::CLOSE_PASSWORD_CHANGED;
+use email_address::EmailAddress;
+use uuid::Uuid;
+
+use crate::config::{AuthConfig, Config};
+use crate::dialect::Dialect;
+use crate::error::DbError;
+use crate::pool::{self, DatabaseConfig, DbPool};
+use crate::repositories::user_repository::NewUser;
+use crate::state::AppState;
+use crate::strategies::notification_strategy::{NewNotification, create_notification};
+use crate::strategies::session_strategy::disconnect_user;
+use crate::strategies::user_strategy::{
+    get_user_by_username, hash_password, insert_user, set_user_flag, set_user_password,
+};
+use crate::{migrate, pubsub, storage};
+
+fn prompt(label: &str) -> Result<String, String> {
+    print!("{}: ", label);
+    io::stdout().flush().map_err(|error| error.to_string())?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line).map_err(|error| error.to_string())?;
+    Ok(line.trim().to_string())
+}
+
+fn env_or_prompt(name: &str, label: &str) -> Result<String, String> {
+    match env::var(name) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        _ => prompt(label),
+    }
+}
+
+fn read_password(name: &str) -> Result<String, String> {
+    if let Ok(password) = env::var(name) {
+        if !password.is_empty() {
+            return Ok(password);
+        }
+    }
+
+    let password = rpassword::prompt_password("Password: ").map_err(|error| error.to_string())?;
+    if password.is_empty() {
+        return Err(String::from("Password cannot be empty"));
+    }
+    let confirmation =
+        rpassword::prompt_password("Confirm password: ").map_err(|error| error.to_string())?;
+    if password != confirmation {
+        return Err(String::from("Passwords do not match"));
+    }
+    Ok(password)
+}
+
+pub async fn connect(database: &DatabaseConfig) -> Result<(DbPool, Dialect), String> {
+    let pool = pool::create_pool(database)
+        .await
+        .map_err(|error| format!("Could not create database pool: {}", error))?;
+    migrate::prepare_database(&pool, database).await;
+    Ok((pool, database.dialect))
+}
+
+async fn find_uuid(pool: &DbPool, dialect: Dialect, username: &str) -> Result<String, String> {
+    match get_user_by_username(pool, dialect, username).await {
+        Ok(user) => Ok(user.uuid.to_string()),
+        Err(DbError::NotFound) => Err(format!("User {} does not exist", username)),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+async fn create_admin(pool: &DbPool, dialect: Dialect, auth: &AuthConfig) -> Result<(), String> {
+    let username = env_or_prompt("ADMIN_USERNAME", "Username")?;
+    let email = env_or_prompt("ADMIN_EMAIL", "Email")?;
+    if username.is_empty() || email.is_empty() {
+        return Err(String::from("Username and email are required"));
+    }
+    if !EmailAddress::is_valid(&email) {
+        return Err(format!("Invalid email address: {}", email));
+    }
+    let password = read_password("ADMIN_PASSWORD")?;
+
+    let new_user = NewUser {
+        uuid: Uuid::new_v4(),
+        username,
+        email,
+        password: hash_password(password, auth.password_salt),
+        is_admin: true,
+    };
+    match insert_user(pool, dialect, &new_user).await {
+        Ok(user) => {
+            println!("Created admin {} ({})", user.username, user.uuid);
+            Ok(())
+        }
+        Err(DbError::UniqueViolation(_)) => {
+            Err(format!("User {} or email {} already exists", new_user.username, new_user.email))
+        }
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+async fn set_admin(
+    pool: &DbPool,
+    dialect: Dialect,
+    username: &str,
+    is_admin: bool,
+) -> Result<(), String> {
+    let uuid = find_uuid(pool, dialect, username).await?;
+    set_user_flag(pool, dialect, "is_admin", &uuid, is_admin)
+        .await
+        .map_err(|error| error.to_string())?;
+    if is_admin {
+        println!("Promoted {} to admin", username);
+    } else {
+        println!("Demoted {} from admin", username);
+    }
+    Ok(())
+}
+
+async fn reset_password(
+    config: Arc<Config>,
+    pool: DbPool,
+    dialect: Dialect,
+    username: &str,
+) -> Result<(), String> {
+    let uuid = find_uuid(&pool, dialect, username).await?;
+    let password = read_password("USER_PASSWORD")?;
+    let password = hash_password(password, config.auth.password_salt);
+    set_user_password(&pool, dialect, &uuid, &password).await.map_err(|error| error.to_string())?;
+
+    let pubsub = pubsub::create_pubsub(&config.database, config.ws.channel_capacity)
+        .await
+        .map_err(|error| format!("Could not create Postgres pub/sub: {}", error))?;
+    let storage = storage::create_storage(&config.storage);
+    let state = AppState::new(config, pool, None, pubsub, storage);
+    let body = String::from("Your password was changed by an administrator");
+    let notification =
+        NewNotification::new(NotificationCategory::Security, "Password changed", body);
+    create_notification(&state, &uuid, notification).await.map_err(|error| error.to_string())?;
+    disconnect_user(state.pubsub.as_ref(), &uuid, CLOSE_PASSWORD_CHANGED, "Password changed");
+    state.pubsub.flush().await;
+    println!("Password reset for {}", username);
+    if !dialect.supports_notify() {
+        println!("Open sessions are not closed on SQLite and end when their token expires");
+    }
+    Ok(())
+}
+
+pub async fn command(config: Arc<Config>, args: &[String]) -> Result<(), String> {
+    let usage = "Usage: dash_server user create-admin|promote|demote|reset-password [username]";
+    let action = match (args.first().map(String::as_str), args.len()) {
+        (Some(action @ "create-admin"), 1) => action,
+        (Some(action @ ("promote" | "demote" | "reset-password")), 2) => action,
+        _ => return Err(String::from(usage)),
+    };
+
+    let (pool, dialect) = connect(&config.database).await?;
+    match action {
+        "create-admin" => create_admin(&pool, dialect, &config.auth).await,
+        "promote" => set_admin(&pool, dialect, &args[1], true).await,
+        "demote" => set_admin(&pool, dialect, &args[1], false).await,
+        _ => reset_password(config, pool, dialect, &args[1]).await,
+    }
+}
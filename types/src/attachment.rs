@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "sqlx")]
+use sqlx::FromRow;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(FromRow))]
+pub struct Attachment {
+    pub id: String,
+    pub uploader_uuid: String,
+    pub message_id: Option<i64>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub hash: String,
+    pub storage_key: String,
+    pub thumbnail_key: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AttachmentInfo {
+    pub id: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub url: String,
+    pub thumbnail_url: Option<String>,
+    pub expires_at: i64,
+}
@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "sqlx")]
+use sqlx::FromRow;
+
+use crate::attachment::AttachmentInfo;
+
+pub const DEFAULT_ROOM: &str = "general";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(FromRow))]
+pub struct ChatMessage {
+    pub id: i64,
+    pub room: String,
+    pub user_uuid: String,
+    pub username: String,
+    pub text: String,
+    pub created_at: i64,
+    pub edited_at: Option<i64>,
+    pub deleted_at: Option<i64>,
+    #[cfg_attr(feature = "sqlx", sqlx(skip))]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<AttachmentInfo>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NewChatMessage {
+    #[serde(default)]
+    pub room: Option<String>,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SanctionKind {
+    Mute,
+    Ban,
+}
+
+impl SanctionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SanctionKind::Mute => "mute",
+            SanctionKind::Ban => "ban",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationActionType {
+    Mute,
+    Unmute,
+    Kick,
+    Ban,
+    Unban,
+    DeleteMessage,
+}
+
+impl ModerationActionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationActionType::Mute => "mute",
+            ModerationActionType::Unmute => "unmute",
+            ModerationActionType::Kick => "kick",
+            ModerationActionType::Ban => "ban",
+            ModerationActionType::Unban => "unban",
+            ModerationActionType::DeleteMessage => "delete_message",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(FromRow))]
+pub struct ModerationAction {
+    pub id: String,
+    pub moderator_uuid: String,
+    pub target_uuid: Option<String>,
+    pub action: String,
+    pub room: Option<String>,
+    pub message_id: Option<i64>,
+    pub reason: Option<String>,
+    pub expires_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(FromRow))]
+pub struct ReadMarker {
+    pub user_uuid: String,
+    pub room: String,
+    pub message_id: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReadMarkerUpdate {
+    pub message_id: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(FromRow))]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct TypingUser {
+    pub uuid: String,
+    pub username: String,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub saturation: f64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct DatabaseStats {
+    pub backend: String,
+    pub primary: PoolStats,
+    pub replica: Option<PoolStats>,
+    pub acquire_timeouts: u64,
+}
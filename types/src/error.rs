@@ -0,0 +1,177 @@
+use std::fmt;
+
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    ValidationFailed,
+    InvalidBody,
+    InvalidPath,
+    InvalidQuery,
+    MissingFields,
+    InvalidEmail,
+    InvalidMessage,
+    NotFound,
+    UserNotExist,
+    MessageNotExist,
+    AttachmentNotExist,
+    NotificationNotExist,
+    Conflict,
+    UserExists,
+    RateLimited,
+    Unauthorized,
+    WrongCredentials,
+    InvalidToken,
+    AccessDenied,
+    UserDisabled,
+    ServerError,
+    TokenGeneration,
+    ServiceUnavailable,
+}
+
+impl ApiErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ApiErrorCode::ValidationFailed
+            | ApiErrorCode::InvalidBody
+            | ApiErrorCode::InvalidPath
+            | ApiErrorCode::InvalidQuery
+            | ApiErrorCode::MissingFields
+            | ApiErrorCode::InvalidEmail
+            | ApiErrorCode::InvalidMessage => StatusCode::BAD_REQUEST,
+            ApiErrorCode::NotFound
+            | ApiErrorCode::UserNotExist
+            | ApiErrorCode::MessageNotExist
+            | ApiErrorCode::AttachmentNotExist
+            | ApiErrorCode::NotificationNotExist => StatusCode::NOT_FOUND,
+            ApiErrorCode::Conflict | ApiErrorCode::UserExists => StatusCode::CONFLICT,
+            ApiErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiErrorCode::Unauthorized | ApiErrorCode::WrongCredentials => StatusCode::UNAUTHORIZED,
+            ApiErrorCode::InvalidToken
+            | ApiErrorCode::AccessDenied
+            | ApiErrorCode::UserDisabled => StatusCode::FORBIDDEN,
+            ApiErrorCode::ServerError | ApiErrorCode::TokenGeneration => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            ApiErrorCode::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            ApiErrorCode::ValidationFailed => "Validation failed",
+            ApiErrorCode::InvalidBody => "Invalid request body",
+            ApiErrorCode::InvalidPath => "Invalid path parameter",
+            ApiErrorCode::InvalidQuery => "Invalid query parameter",
+            ApiErrorCode::MissingFields => "Missing required fields",
+            ApiErrorCode::InvalidEmail => "Invalid email address",
+            ApiErrorCode::InvalidMessage => "Invalid message",
+            ApiErrorCode::NotFound => "Not found",
+            ApiErrorCode::UserNotExist => "User does not exist",
+            ApiErrorCode::MessageNotExist => "Message does not exist",
+            ApiErrorCode::AttachmentNotExist => "Attachment does not exist",
+            ApiErrorCode::NotificationNotExist => "Notification does not exist",
+            ApiErrorCode::Conflict => "Conflict",
+            ApiErrorCode::UserExists => "User already exists",
+            ApiErrorCode::RateLimited => "Rate limit exceeded",
+            ApiErrorCode::Unauthorized => "Authentication required",
+            ApiErrorCode::WrongCredentials => "Wrong credentials",
+            ApiErrorCode::InvalidToken => "Invalid token",
+            ApiErrorCode::AccessDenied => "Access denied",
+            ApiErrorCode::UserDisabled => "User is disabled",
+            ApiErrorCode::ServerError => "Server error",
+            ApiErrorCode::TokenGeneration => "Token generation error",
+            ApiErrorCode::ServiceUnavailable => "Service temporarily unavailable",
+        }
+    }
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::String(code)) => write!(f, "{}", code),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self { field: field.to_string(), message: message.to_string() }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    pub code: ApiErrorCode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: ApiErrorCode,
+    pub detail: Option<String>,
+    pub errors: Vec<FieldError>,
+}
+
+impl ApiError {
+    pub fn from_code(code: ApiErrorCode) -> Self {
+        Self { status: code.status(), code, detail: None, errors: Vec::new() }
+    }
+
+    pub fn with_detail(code: ApiErrorCode, detail: String) -> Self {
+        Self { detail: Some(detail), ..Self::from_code(code) }
+    }
+
+    pub fn validation(errors: Vec<FieldError>) -> Self {
+        Self { errors, ..Self::from_code(ApiErrorCode::ValidationFailed) }
+    }
+
+    pub fn detail(&self) -> String {
+        self.detail.clone().unwrap_or_else(|| self.code.title().to_string())
+    }
+
+    pub fn to_problem(
+        &self,
+        request_id: Option<String>,
+        instance: Option<String>,
+    ) -> ProblemDetails {
+        ProblemDetails {
+            problem_type: format!("urn:dash:problem:{}", self.code),
+            title: self.code.title().to_string(),
+            status: self.status.as_u16(),
+            detail: self.detail.clone(),
+            instance,
+            code: self.code,
+            request_id,
+            errors: self.errors.clone(),
+        }
+    }
+}
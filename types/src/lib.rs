@@ -1,2 +1,9 @@
+pub mod attachment;
 pub mod auth;
+pub mod chat;
+pub mod database;
+pub mod error;
+pub mod health;
+pub mod notification;
 pub mod user;
+pub mod ws;
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "sqlx")]
+use sqlx::FromRow;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    Security,
+    Account,
+    Mention,
+    Moderation,
+    System,
+}
+
+impl NotificationCategory {
+    pub const ALL: [NotificationCategory; 5] = [
+        NotificationCategory::Security,
+        NotificationCategory::Account,
+        NotificationCategory::Mention,
+        NotificationCategory::Moderation,
+        NotificationCategory::System,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationCategory::Security => "security",
+            NotificationCategory::Account => "account",
+            NotificationCategory::Mention => "mention",
+            NotificationCategory::Moderation => "moderation",
+            NotificationCategory::System => "system",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "sqlx", derive(FromRow))]
+pub struct Notification {
+    pub id: String,
+    pub user_uuid: String,
+    pub category: String,
+    pub title: String,
+    pub body: String,
+    pub room: Option<String>,
+    pub message_id: Option<i64>,
+    pub read_at: Option<i64>,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NotificationList {
+    pub notifications: Vec<Notification>,
+    pub unread: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct NotificationPreference {
+    pub category: NotificationCategory,
+    pub enabled: bool,
+}
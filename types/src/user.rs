@@ -1,28 +1,70 @@
 use std::fmt;
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use email_address::EmailAddress;
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "sqlx")]
 use sqlx::any::AnyRow;
 #[cfg(feature = "sqlx")]
 use sqlx::{FromRow, Row};
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+use uuid::Uuid;
 
 #[derive(Clone, Debug, Serialize)]
 pub struct User {
     pub id: i32,
-    pub uuid: String,
+    pub uuid: Uuid,
     pub username: String,
     pub email: EmailAddress,
     pub password: String,
     pub is_admin: bool,
+    pub is_moderator: bool,
+    pub is_disabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl User {
+    pub fn can_moderate(&self) -> bool {
+        self.is_admin || self.is_moderator
+    }
+}
+
+#[cfg(feature = "sqlx")]
+fn try_get_bool(row: &AnyRow, column: &str) -> Result<Option<bool>, sqlx::Error> {
+    match row.try_get::<Option<bool>, &str>(column) {
+        Ok(value) => Ok(value),
+        Err(sqlx::Error::ColumnDecode { .. }) => {
+            Ok(row.try_get::<Option<i64>, &str>(column)?.map(|value| value != 0))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(feature = "sqlx")]
+fn try_get_uuid(row: &AnyRow, column: &str) -> Result<Uuid, sqlx::Error> {
+    let value: String = row.try_get(column)?;
+    Uuid::parse_str(&value).map_err(|error| sqlx::Error::ColumnDecode {
+        index: column.to_string(),
+        source: error.into(),
+    })
+}
+
+#[cfg(feature = "sqlx")]
+fn try_get_timestamp(row: &AnyRow, column: &str) -> Result<DateTime<Utc>, sqlx::Error> {
+    let value: String = row.try_get(column)?;
+    DateTime::parse_from_rfc3339(&value).map(|timestamp| timestamp.with_timezone(&Utc)).map_err(
+        |error| sqlx::Error::ColumnDecode { index: column.to_string(), source: error.into() },
+    )
 }
 
 #[cfg(feature = "sqlx")]
 impl<'r> FromRow<'r, AnyRow> for User {
     fn from_row(row: &AnyRow) -> Result<Self, sqlx::Error> {
         let id: i32 = row.try_get("id")?;
-        let uuid: String = row.try_get("uuid")?;
+        let uuid = try_get_uuid(row, "uuid")?;
         let username: String = row.try_get("username")?;
         let email: EmailAddress = match row.try_get::<String, &str>("email") {
             Ok(address) => EmailAddress::new_unchecked(address),
@@ -32,15 +74,32 @@ impl<'r> FromRow<'r, AnyRow> for User {
             }
         };
         let password: String = row.try_get("password")?;
-        let is_admin = row.try_get("is_admin")?;
+        let is_admin = try_get_bool(row, "is_admin")?.unwrap_or(false);
+        let is_moderator = try_get_bool(row, "is_moderator")?.unwrap_or(false);
+        let is_disabled = try_get_bool(row, "is_disabled")?.unwrap_or(false);
+        let created_at = try_get_timestamp(row, "created_at")?;
+        let updated_at = try_get_timestamp(row, "updated_at")?;
 
-        Ok(Self { id, uuid, username, email, password, is_admin })
+        Ok(Self {
+            id,
+            uuid,
+            username,
+            email,
+            password,
+            is_admin,
+            is_moderator,
+            is_disabled,
+            created_at,
+            updated_at,
+        })
     }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct RegisterUser {
     pub username: String,
+    #[cfg_attr(feature = "openapi", schema(value_type = String, format = Email))]
     pub email: EmailAddress,
     pub password: String,
 }
@@ -79,6 +138,7 @@ impl RegisterUser {
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct LoginUser {
     pub username: String,
     pub password: String,
@@ -109,11 +169,24 @@ impl LoginUser {
 }
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
-#[cfg_attr(feature = "sqlx", derive(FromRow))]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct UserInfo {
-    pub uuid: String,
+    pub uuid: Uuid,
     pub username: String,
     pub is_admin: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "sqlx")]
+impl<'r> FromRow<'r, AnyRow> for UserInfo {
+    fn from_row(row: &AnyRow) -> Result<Self, sqlx::Error> {
+        let uuid = try_get_uuid(row, "uuid")?;
+        let username: String = row.try_get("username")?;
+        let is_admin = try_get_bool(row, "is_admin")?.unwrap_or(false);
+        let created_at = try_get_timestamp(row, "created_at")?;
+
+        Ok(Self { uuid, username, is_admin, created_at })
+    }
 }
 
 impl fmt::Display for UserInfo {
@@ -124,10 +197,20 @@ impl fmt::Display for UserInfo {
 
 impl UserInfo {
     pub fn new() -> Self {
-        Self { uuid: String::new(), username: String::new(), is_admin: false }
+        Self {
+            uuid: Uuid::nil(),
+            username: String::new(),
+            is_admin: false,
+            created_at: DateTime::default(),
+        }
     }
 
     pub fn from_user(user: User) -> Self {
-        Self { uuid: user.uuid, username: user.username, is_admin: user.is_admin }
+        Self {
+            uuid: user.uuid,
+            username: user.username,
+            is_admin: user.is_admin,
+            created_at: user.created_at,
+        }
     }
 }
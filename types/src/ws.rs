@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+use crate::chat::{ChatMessage, ReactionCount};
+use crate::notification::Notification;
+
+pub const CLOSE_IDLE_TIMEOUT: u16 = 4000;
+pub const CLOSE_TOKEN_EXPIRED: u16 = 4001;
+pub const CLOSE_USER_REMOVED: u16 = 4002;
+pub const CLOSE_USER_DISABLED: u16 = 4003;
+pub const CLOSE_SLOW_CONSUMER: u16 = 4004;
+pub const CLOSE_KICKED: u16 = 4005;
+pub const CLOSE_BANNED: u16 = 4006;
+pub const CLOSE_PASSWORD_CHANGED: u16 = 4007;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct WsTicket {
+    pub ticket: String,
+    pub expires_in: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsClientMessage {
+    Auth {
+        token: String,
+    },
+    Join {
+        room: String,
+    },
+    Leave {
+        room: String,
+    },
+    Chat {
+        #[serde(default)]
+        room: Option<String>,
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        attachments: Vec<String>,
+    },
+    History {
+        #[serde(default)]
+        room: Option<String>,
+        after: Option<i64>,
+    },
+    Typing {
+        #[serde(default)]
+        room: Option<String>,
+    },
+    Read {
+        #[serde(default)]
+        room: Option<String>,
+        message_id: i64,
+    },
+    React {
+        id: i64,
+        emoji: String,
+    },
+    Unreact {
+        id: i64,
+        emoji: String,
+    },
+    Edit {
+        id: i64,
+        text: String,
+    },
+    Delete {
+        id: i64,
+    },
+    Mute {
+        uuid: String,
+        #[serde(default)]
+        room: Option<String>,
+        duration: u64,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    Unmute {
+        uuid: String,
+        #[serde(default)]
+        room: Option<String>,
+    },
+    Kick {
+        uuid: String,
+        #[serde(default)]
+        room: Option<String>,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    Ban {
+        uuid: String,
+        #[serde(default)]
+        room: Option<String>,
+        #[serde(default)]
+        duration: Option<u64>,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    Unban {
+        uuid: String,
+        #[serde(default)]
+        room: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerMessage {
+    Joined { room: String, username: String },
+    Left { room: String, username: String },
+    Chat(ChatMessage),
+    Edited { id: i64, room: String, text: String, edited_at: i64 },
+    Deleted { id: i64, room: String, deleted_by: String },
+    Typing { room: String, uuid: String, username: String, expires_in: u64 },
+    Read { room: String, uuid: String, message_id: i64 },
+    Reactions { id: i64, room: String, reactions: Vec<ReactionCount> },
+    Muted { uuid: String, room: Option<String>, until: i64, reason: Option<String> },
+    Unmuted { uuid: String, room: Option<String> },
+    Kicked { uuid: String, room: Option<String>, reason: Option<String> },
+    Banned { uuid: String, room: Option<String>, until: Option<i64>, reason: Option<String> },
+    Unbanned { uuid: String, room: Option<String> },
+    History { room: String, messages: Vec<ChatMessage> },
+    Notification(Notification),
+    Lagged { missed: u64 },
+    AuthExtended { exp: u64 },
+    Error { message: String },
+    Disconnected { uuid: String, code: u16, reason: String },
+}
+
+impl WsServerMessage {
+    pub fn room(&self) -> Option<&str> {
+        match self {
+            WsServerMessage::Joined { room, .. }
+            | WsServerMessage::Left { room, .. }
+            | WsServerMessage::Edited { room, .. }
+            | WsServerMessage::Deleted { room, .. }
+            | WsServerMessage::Typing { room, .. }
+            | WsServerMessage::Read { room, .. }
+            | WsServerMessage::Reactions { room, .. }
+            | WsServerMessage::History { room, .. } => Some(room),
+            WsServerMessage::Chat(message) => Some(&message.room),
+            WsServerMessage::Muted { room, .. }
+            | WsServerMessage::Unmuted { room, .. }
+            | WsServerMessage::Kicked { room, .. }
+            | WsServerMessage::Banned { room, .. }
+            | WsServerMessage::Unbanned { room, .. } => room.as_deref(),
+            WsServerMessage::Notification(_)
+            | WsServerMessage::Lagged { .. }
+            | WsServerMessage::AuthExtended { .. }
+            | WsServerMessage::Error { .. }
+            | WsServerMessage::Disconnected { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct WsStats {
+    pub connections: u64,
+    pub lagged_clients: u64,
+    pub missed_messages: u64,
+    pub dropped_clients: u64,
+}